@@ -1,68 +1,466 @@
 use std::str::FromStr;
 use std::fmt::Debug;
 
-pub struct Config {
-    pub board_width: usize,
-    pub board_height: usize,
-    pub dead_cell: char,
-    pub alive_cell: char,
-    pub is_rand: bool,
-    pub save_name: Option<String>,
+use crate::life::chance::Chance;
+use crate::life::formats::Format;
+use crate::life::image;
+use crate::life::rule::Rule;
+use crate::life::soup::Soup;
+use crate::life::svg::{Region, Shape};
+use crate::life::theme::Theme;
+use crate::life::Topology;
+use crate::settings::{self, Settings};
+
+pub enum Command {
+    /// Open the editor before simulating
+    Edit(Config),
+    /// Start simulating straight away
+    Run(Config),
+    Batch(BatchConfig),
+    Convert(ConvertConfig),
+    Info(InfoConfig),
+    Bench(BenchConfig),
+    Export(ExportConfig),
+    Replay(ReplayConfig),
+    Help(String),
 }
 
-impl Config {
-    pub fn new(args: &[String]) -> Self {
-        let len = args.len();
-        let use_args = len == 6 || len == 7;
+struct Flag {
+    long: &'static str,
+    short: Option<char>,
+    /// Name of the flag's value, or `None` for switches
+    value: Option<&'static str>,
+    help: &'static str,
+}
+
+struct Subcommand {
+    name: &'static str,
+    about: &'static str,
+    positional: &'static [&'static str],
+    flags: &'static [&'static [Flag]],
+}
+
+const BOARD_FLAGS: &[Flag] = &[
+    Flag { long: "width", short: Some('w'), value: Some("N"), help: "board width in cells (0 for fullscreen)" },
+    Flag { long: "height", short: Some('H'), value: Some("N"), help: "board height in cells (0 for fullscreen)" },
+    Flag { long: "load", short: Some('l'), value: Some("PATTERN"), help: "pattern file, or the name of a board in the save directory" },
+    Flag { long: "random", short: Some('r'), value: None, help: "fill the board randomly" },
+    Flag { long: "seed", short: None, value: Some("N"), help: "seed for the random fill, implies --random" },
+    Flag { long: "density", short: None, value: Some("P"), help: "chance of a random cell being alive (default 0.5)" },
+    Flag { long: "soup-size", short: None, value: Some("WxH"), help: "only fill a centered region of this size" },
+    Flag { long: "symmetry", short: None, value: Some("SYM"), help: "soup symmetry: C1 (default), C2, C4, D2, D4 or D8" },
+];
+
+const RULE_FLAG: &[Flag] = &[
+    Flag { long: "rule", short: None, value: Some("RULE"), help: "rule such as B3/S23 (default), B2-a3/S12-k3, B2/S/C3, B2/S34H, R5,C0,M1,S34..58,B34..45,NM, W110, RL, critters, immigration, quadlife, lenia, wireworld or a .rule file" },
+];
+
+const CHANCE_FLAGS: &[Flag] = &[
+    Flag { long: "chances", short: None, value: Some("SPEC"), help: "birth and survival chances by neighbor count, such as b3=0.9,s23=0.5,seed=7" },
+    Flag { long: "noise", short: None, value: Some("P"), help: "chance of each cell flipping every tick" },
+];
+
+const TOPOLOGY_FLAG: &[Flag] = &[
+    Flag { long: "topology", short: Some('t'), value: Some("TOPOLOGY"), help: "bounded (default) or torus" },
+];
+
+const DISPLAY_FLAGS: &[Flag] = &[
+    Flag { long: "dead-char", short: None, value: Some("CHAR"), help: "character drawn for dead cells (default ' ')" },
+    Flag { long: "alive-char", short: None, value: Some("CHAR"), help: "character drawn for alive cells (default '#')" },
+    Flag { long: "dying-char", short: None, value: Some("CHAR"), help: "character drawn for the dying states of Generations rules" },
+    Flag { long: "state-chars", short: None, value: Some("CHARS"), help: "characters drawn for each state of a rule, starting with dead" },
+    Flag { long: "delay", short: Some('d'), value: Some("MS"), help: "delay between ticks in milliseconds (default 64)" },
+    Flag { long: "theme", short: None, value: Some("NAME"), help: "color theme: plain, classic, ocean, fire, matrix or mono" },
+    Flag { long: "alive-color", short: None, value: Some("COLOR"), help: "color of alive cells, a name or #rrggbb" },
+    Flag { long: "dead-color", short: None, value: Some("COLOR"), help: "color of dead cells" },
+    Flag { long: "background", short: None, value: Some("COLOR"), help: "background color of the board" },
+    Flag { long: "age-colors", short: None, value: None, help: "color alive cells by how long they have been alive" },
+    Flag { long: "trail", short: None, value: Some("N"), help: "show dying cells fading out over N generations" },
+];
+
+const RECORD_FLAGS: &[Flag] = &[
+    Flag { long: "record", short: None, value: Some("FILE"), help: "record the session as an asciicast, to play back with asciinema" },
+    Flag { long: "journal", short: None, value: Some("FILE"), help: "write every input of the session to a journal, to play back with replay" },
+];
+
+const REPLAY_FLAGS: &[Flag] = &[
+    Flag { long: "visual", short: None, value: None, help: "show the replay on the terminal, then carry on from where the journal ends" },
+    Flag { long: "output", short: Some('o'), value: Some("FILE"), help: "write the final board to FILE instead of stdout" },
+];
+
+const GENERATIONS_FLAG: &[Flag] = &[
+    Flag { long: "generations", short: Some('n'), value: Some("N"), help: "number of generations to run" },
+];
+
+const FORMAT_FLAG: &[Flag] = &[
+    Flag { long: "format", short: Some('f'), value: Some("FORMAT"), help: "output format: cells, rle or life" },
+];
+
+const BATCH_FLAGS: &[Flag] = &[
+    Flag { long: "until-stable", short: None, value: None, help: "stop early once the board repeats a recent state" },
+    Flag { long: "output", short: Some('o'), value: Some("FILE"), help: "write the result to FILE instead of stdout" },
+];
 
-        if !use_args && len != 1 {
-            println!("USAGE: {} [width] [height] [dead_cell] [alive_cell] [is_rand] OPTIONAL: [save_file]\nNOTE: use these chars in place of ones that can't be used in cmd args (`_` => ' ', 'h' => '#', 'a' => '`', 't' => '@')\nSet the width and height to 0 for fullscreen", args[0]);
-            std::process::exit(-1);
-        } else if len == 1 {
-            println!("Set the width and height to 0 for fullscreen");
+const IMAGE_FLAGS: &[Flag] = &[
+    Flag { long: "cell-size", short: Some('s'), value: Some("N"), help: "width and height of a cell in pixels (default 8)" },
+    Flag { long: "grid", short: None, value: None, help: "draw lines between cells" },
+    Flag { long: "grid-color", short: None, value: Some("COLOR"), help: "color of the grid lines, implies --grid (default #c8c8c8)" },
+    Flag { long: "delay", short: Some('d'), value: Some("MS"), help: "time each frame of a GIF is shown in milliseconds (default 100)" },
+    Flag { long: "theme", short: None, value: Some("NAME"), help: "color theme: plain, classic, ocean, fire, matrix or mono" },
+    Flag { long: "alive-color", short: None, value: Some("COLOR"), help: "color of alive cells, a name or #rrggbb" },
+    Flag { long: "dead-color", short: None, value: Some("COLOR"), help: "color of dead cells" },
+];
+
+const SVG_FLAGS: &[Flag] = &[
+    Flag { long: "shape", short: None, value: Some("SHAPE"), help: "SVG cell shape: square (default) or circle" },
+    Flag { long: "highlight", short: None, value: Some("X,Y,W,H"), help: "outline a region of cells in an SVG" },
+    Flag { long: "arrows", short: None, value: None, help: "draw an arrow over each spaceship in an SVG, pointing the way it travels" },
+];
+
+const SUBCOMMANDS: &[Subcommand] = &[
+    Subcommand {
+        name: "edit",
+        about: "edit a board and then simulate it (the default)",
+        positional: &[],
+        flags: &[BOARD_FLAGS, RULE_FLAG, CHANCE_FLAGS, TOPOLOGY_FLAG, DISPLAY_FLAGS, RECORD_FLAGS],
+    },
+    Subcommand {
+        name: "run",
+        about: "simulate a board straight away, press r to go to the editor",
+        positional: &[],
+        flags: &[BOARD_FLAGS, RULE_FLAG, CHANCE_FLAGS, TOPOLOGY_FLAG, DISPLAY_FLAGS, RECORD_FLAGS],
+    },
+    Subcommand {
+        name: "batch",
+        about: "run a pattern without the terminal UI and write the result",
+        positional: &["PATTERN"],
+        flags: &[GENERATIONS_FLAG, BATCH_FLAGS, FORMAT_FLAG, RULE_FLAG, CHANCE_FLAGS, TOPOLOGY_FLAG],
+    },
+    Subcommand {
+        name: "convert",
+        about: "convert a pattern between the life, cells and rle formats",
+        positional: &["INPUT", "OUTPUT"],
+        flags: &[FORMAT_FLAG, RULE_FLAG],
+    },
+    Subcommand {
+        name: "info",
+        about: "print the size, population and fate of a pattern",
+        positional: &["PATTERN"],
+        flags: &[GENERATIONS_FLAG, RULE_FLAG, TOPOLOGY_FLAG],
+    },
+    Subcommand {
+        name: "bench",
+        about: "measure how fast generations are computed",
+        positional: &[],
+        flags: &[BOARD_FLAGS, RULE_FLAG, CHANCE_FLAGS, TOPOLOGY_FLAG, GENERATIONS_FLAG],
+    },
+    Subcommand {
+        name: "replay",
+        about: "play back a journal and check it ends with the same board",
+        positional: &["JOURNAL"],
+        flags: &[REPLAY_FLAGS],
+    },
+    Subcommand {
+        name: "export",
+        about: "run a pattern and draw it as a PNG or SVG, numbered frames of either, or an animated GIF",
+        positional: &["PATTERN", "OUTPUT"],
+        flags: &[GENERATIONS_FLAG, IMAGE_FLAGS, SVG_FLAGS, RULE_FLAG, CHANCE_FLAGS, TOPOLOGY_FLAG],
+    },
+];
+
+const HELP_FLAG: Flag = Flag { long: "help", short: Some('h'), value: None, help: "print this help" };
+
+/// Flags and positional arguments given to a subcommand
+struct Matches {
+    values: Vec<(&'static str, Option<String>)>,
+    positional: Vec<String>,
+}
+
+impl Matches {
+    fn has(&self, long: &str) -> bool {
+        self.values.iter().any(|(name, _)| *name == long)
+    }
+
+    fn get<T: FromStr>(&self, long: &str) -> Result<Option<T>, String> {
+        match self.values.iter().rev().find(|(name, _)| *name == long) {
+            Some((_, Some(value))) => value
+                .parse()
+                .map(Some)
+                .map_err(|_| format!("Failed to parse --{} value `{}`", long, value)),
+            _ => Ok(None),
         }
+    }
 
-        let board_width = if use_args {
-            args[1].parse().expect("Failed to parse width")
-        } else {
-            Self::input("Enter a board width:")
+    fn get_rule(&self, default: Rule) -> Result<Rule, String> {
+        match self.values.iter().rev().find(|(name, _)| *name == "rule") {
+            Some((_, Some(rule))) => rule.parse(),
+            _ => Ok(default),
+        }
+    }
+
+    /// The random fill described by the soup flags, if any of them were given
+    fn get_soup(&self) -> Result<Option<Soup>, String> {
+        let flags = ["random", "seed", "density", "soup-size", "symmetry"];
+        if !flags.iter().any(|flag| self.has(flag)) {
+            return Ok(None);
+        }
+
+        let mut soup = Soup::new(self.get("seed")?);
+        if let Some(density) = self.get::<f64>("density")? {
+            if !(0.0..=1.0).contains(&density) {
+                return Err(String::from("--density must be between 0 and 1"));
+            }
+            soup.density = density;
+        }
+
+        if let Some(size) = self.get::<String>("soup-size")? {
+            let parsed = size
+                .split_once('x')
+                .and_then(|(w, h)| Some((w.parse().ok()?, h.parse().ok()?)));
+            soup.region = Some(parsed.ok_or_else(|| format!("Invalid --soup-size `{}`, expected WxH", size))?);
+        }
+
+        if let Some((_, Some(symmetry))) = self.values.iter().rev().find(|(name, _)| *name == "symmetry") {
+            soup.symmetry = symmetry.parse()?;
+        }
+
+        Ok(Some(soup))
+    }
+
+    /// The chances and noise given by `--chances` and `--noise`, if either was,
+    /// checked against the `rule` they go with
+    fn get_chance(&self, rule: &Rule) -> Result<Option<Chance>, String> {
+        if !self.has("chances") && !self.has("noise") {
+            return Ok(None);
+        }
+
+        let mut chance = Chance::new(None);
+        if let Some(spec) = self.get::<String>("chances")? {
+            chance.apply(&spec)?;
+        }
+        if let Some(noise) = self.get::<String>("noise")? {
+            chance.apply(&format!("noise={}", noise))?;
+        }
+        chance.check(rule)?;
+
+        Ok(Some(chance))
+    }
+
+    /// `default` with any of the color flags applied on top
+    fn get_theme(&self, default: Theme) -> Result<Theme, String> {
+        let mut theme = match self.get::<String>("theme")? {
+            Some(name) => settings::named_theme(&name)?,
+            None => default,
         };
 
-        let board_height = if use_args {
-            args[2].parse().expect("Failed to parse height")
+        let color = |flag| self.get::<String>(flag)?.map(|c| settings::parse_color(&c)).transpose();
+        if let Some(alive) = color("alive-color")? {
+            theme.alive = Some(alive);
+        }
+        if let Some(dead) = color("dead-color")? {
+            theme.dead = Some(dead);
+        }
+        if let Some(background) = color("background")? {
+            theme.background = Some(background);
+        }
+        if self.has("age-colors") {
+            theme.color_by_age = true;
+        }
+        if let Some(trail) = self.get("trail")? {
+            theme.trail = trail;
+        }
+
+        Ok(theme)
+    }
+
+    fn get_topology(&self, default: Topology) -> Result<Topology, String> {
+        match self.values.iter().rev().find(|(name, _)| *name == "topology") {
+            Some((_, Some(topology))) => topology.parse(),
+            _ => Ok(default),
+        }
+    }
+}
+
+/// Parses the command line, using `settings` for anything not given
+pub fn parse(args: &[String], settings: &Settings) -> Result<Command, String> {
+    let program = args.first().map_or("game_of_life", String::as_str);
+
+    if args.len() == 1 {
+        return Ok(Command::Edit(Config::prompt(settings)));
+    }
+
+    let (subcommand, rest) = match SUBCOMMANDS.iter().find(|s| s.name == args[1]) {
+        Some(subcommand) => (subcommand, &args[2..]),
+        None if ["--help", "-h", "help"].contains(&args[1].as_str()) => return Ok(Command::Help(help(program))),
+        None if args[1].starts_with('-') => (&SUBCOMMANDS[0], &args[1..]),
+        None => return Err(format!("Unknown command `{}`. See `{} --help`", args[1], program)),
+    };
+
+    let matches = parse_flags(subcommand, rest)?;
+    if matches.has("help") {
+        return Ok(Command::Help(subcommand_help(program, subcommand)));
+    }
+
+    if matches.positional.len() != subcommand.positional.len() {
+        return Err(format!(
+            "Expected {} argument(s) for `{}`: {}",
+            subcommand.positional.len(),
+            subcommand.name,
+            subcommand.positional.join(" ")
+        ));
+    }
+
+    Ok(match subcommand.name {
+        "edit" => Command::Edit(Config::from_matches(&matches, settings)?),
+        "run" => Command::Run(Config::from_matches(&matches, settings)?),
+        "batch" => Command::Batch(BatchConfig::from_matches(matches, settings)?),
+        "convert" => Command::Convert(ConvertConfig::from_matches(matches, settings)?),
+        "info" => Command::Info(InfoConfig::from_matches(matches, settings)?),
+        "bench" => Command::Bench(BenchConfig::from_matches(&matches, settings)?),
+        "export" => Command::Export(ExportConfig::from_matches(matches, settings)?),
+        "replay" => Command::Replay(ReplayConfig::from_matches(matches)?),
+        _ => unreachable!(),
+    })
+}
+
+fn parse_flags(subcommand: &Subcommand, args: &[String]) -> Result<Matches, String> {
+    let mut matches = Matches { values: Vec::new(), positional: Vec::new() };
+    let flags = || subcommand.flags.iter().flat_map(|group| group.iter()).chain([&HELP_FLAG]);
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let flag = if let Some(long) = arg.strip_prefix("--") {
+            flags().find(|flag| flag.long == long)
+        } else if arg.len() == 2 && arg.starts_with('-') {
+            flags().find(|flag| flag.short == arg.chars().nth(1))
         } else {
-            Self::input("Enter a board height:")
+            matches.positional.push(arg.clone());
+            continue;
         };
 
-        let dead_cell = if use_args {
-            args[3].parse().expect("Failed to parse dead cell char")
-        } else {
-            Self::input("Enter a dead cell char:")
+        let flag = flag.ok_or_else(|| format!("Unknown flag `{}` for `{}`", arg, subcommand.name))?;
+        let value = match flag.value {
+            Some(_) => Some(
+                args.next()
+                    .cloned()
+                    .ok_or_else(|| format!("Missing value for --{}", flag.long))?,
+            ),
+            None => None,
         };
 
-        let alive_cell = if use_args {
-            args[4].parse().expect("Failed to parse alive cell char")
-        } else {
-            Self::input("Enter an alive cell char:")
+        matches.values.push((flag.long, value));
+    }
+
+    Ok(matches)
+}
+
+fn help(program: &str) -> String {
+    let mut out = format!("USAGE: {} [COMMAND] [OPTIONS]\n\nCommands:\n", program);
+
+    for subcommand in SUBCOMMANDS {
+        out.push_str(&format!("  {:<10}{}\n", subcommand.name, subcommand.about));
+    }
+
+    out.push_str(&format!(
+        "\nRun `{} COMMAND --help` for the options of a command.\nWith no arguments at all, the board is configured through prompts.",
+        program
+    ));
+    out
+}
+
+fn subcommand_help(program: &str, subcommand: &Subcommand) -> String {
+    let mut out = format!("{}\n\nUSAGE: {} {}", subcommand.about, program, subcommand.name);
+    for positional in subcommand.positional {
+        out.push_str(&format!(" <{}>", positional));
+    }
+    out.push_str(" [OPTIONS]\n\nOptions:\n");
+
+    for flag in subcommand.flags.iter().flat_map(|group| group.iter()).chain([&HELP_FLAG]) {
+        let short = flag.short.map_or(String::from("    "), |c| format!("-{}, ", c));
+        let long = match flag.value {
+            Some(value) => format!("--{} <{}>", flag.long, value),
+            None => format!("--{}", flag.long),
         };
+        out.push_str(&format!("  {}{:<24}{}\n", short, long, flag.help));
+    }
 
-        let is_rand = if use_args {
-            args[5].parse().expect("Failed to parse is_rand")
-        } else {
-            Self::input("Enter a boolean for whether or not the board should be randomized")
+    out
+}
+
+pub struct Config {
+    pub board_width: usize,
+    pub board_height: usize,
+    pub dead_cell: char,
+    pub alive_cell: char,
+    pub dying_cell: char,
+    /// Characters drawn for each state, overriding the ones above
+    pub state_chars: String,
+    /// Random fill for the board, if it should be randomized
+    pub soup: Option<Soup>,
+    pub rule: Rule,
+    pub chance: Option<Chance>,
+    pub topology: Topology,
+    pub theme: Theme,
+    /// Delay between ticks in milliseconds
+    pub delay: u64,
+    pub save_name: Option<String>,
+    /// Asciicast file the session is recorded to
+    pub record: Option<String>,
+    /// File every input of the session is written to
+    pub journal: Option<String>,
+}
+
+impl Config {
+    fn from_matches(matches: &Matches, settings: &Settings) -> Result<Self, String> {
+        let rule = matches.get_rule(settings.rule.clone())?;
+        let chance = matches.get_chance(&rule)?;
+        Ok(Self {
+            board_width: matches.get("width")?.unwrap_or(settings.board_width),
+            board_height: matches.get("height")?.unwrap_or(settings.board_height),
+            dead_cell: matches.get("dead-char")?.unwrap_or(settings.dead_cell),
+            alive_cell: matches.get("alive-char")?.unwrap_or(settings.alive_cell),
+            dying_cell: matches.get("dying-char")?.unwrap_or(settings.dying_cell),
+            state_chars: matches.get("state-chars")?.unwrap_or_else(|| settings.state_chars.clone()),
+            soup: matches.get_soup()?,
+            rule,
+            chance,
+            topology: matches.get_topology(settings.topology)?,
+            theme: matches.get_theme(settings.theme)?,
+            delay: matches.get("delay")?.unwrap_or(settings.delay),
+            save_name: matches.get("load")?,
+            record: matches.get("record")?,
+            journal: matches.get("journal")?,
+        })
+    }
+
+    /// Interactive fallback used when no arguments are given
+    fn prompt(settings: &Settings) -> Self {
+        println!("Set the width and height to 0 for fullscreen");
+
+        let board_width = Self::input("Enter a board width:");
+        let board_height = Self::input("Enter a board height:");
+        let dead_cell = Self::input("Enter a dead cell char:");
+        let alive_cell = Self::input("Enter an alive cell char:");
+        let is_rand: bool = Self::input("Enter a boolean for whether or not the board should be randomized");
+        let rule = loop {
+            let rule: String = Self::input(&format!("Enter a rule or press enter for {}:", settings.rule));
+            if rule.is_empty() {
+                break settings.rule.clone();
+            }
+
+            match rule.parse() {
+                Ok(rule) => break rule,
+                Err(e) => println!("{}", e),
+            }
         };
 
-        let save_name = match len {
-            1 => {
-                let ret = Self::input("Please enter a save name or press enter to skip: ");
-                if ret == "" {
-                    None
-                } else {
-                    Some(ret)
-                }
+        let save_name = {
+            let ret: String = Self::input("Please enter a save name or press enter to skip: ");
+            if ret.is_empty() {
+                None
+            } else {
+                Some(ret)
             }
-            7 => Some(args[6].parse().expect("Failed to parse save name")),
-            _ => None,
         };
 
         Self {
@@ -70,8 +468,17 @@ impl Config {
             board_height,
             dead_cell,
             alive_cell,
-            is_rand,
-            save_name
+            dying_cell: settings.dying_cell,
+            state_chars: settings.state_chars.clone(),
+            soup: if is_rand { Some(Soup::new(None)) } else { None },
+            rule,
+            chance: None,
+            topology: settings.topology,
+            theme: settings.theme,
+            delay: settings.delay,
+            save_name,
+            record: None,
+            journal: None,
         }
     }
 
@@ -85,12 +492,226 @@ impl Config {
             let mut in_str = String::new();
             std::io::stdin().read_line(&mut in_str).unwrap();
             let pred = ['\n', '\r'];
-            let parsed = in_str.trim_matches(&pred[..]).parse();
-            if parsed.is_err() {
-                println!("Failed to parse. Please try again.");
-            } else {
-                break parsed.unwrap();
+            match in_str.trim_matches(&pred[..]).parse() {
+                Ok(parsed) => break parsed,
+                Err(_) => println!("Failed to parse. Please try again."),
             }
         }
     }
-}
\ No newline at end of file
+}
+
+pub struct BatchConfig {
+    pub pattern: String,
+    pub generations: usize,
+    pub until_stable: bool,
+    pub format: Format,
+    pub rule: Rule,
+    pub chance: Option<Chance>,
+    pub topology: Topology,
+    pub output: Option<String>,
+}
+
+impl BatchConfig {
+    fn from_matches(mut matches: Matches, settings: &Settings) -> Result<Self, String> {
+        let format = matches.get("format")?.unwrap_or(Format::Cells);
+        let output = matches.get("output")?;
+
+        if format == Format::Life && output.is_none() {
+            return Err(String::from("The `life` format can only be written with --output"));
+        }
+
+        let rule = matches.get_rule(settings.rule.clone())?;
+        let chance = matches.get_chance(&rule)?;
+        Ok(Self {
+            pattern: matches.positional.remove(0),
+            generations: matches.get("generations")?.unwrap_or(100),
+            until_stable: matches.has("until-stable"),
+            format,
+            rule,
+            chance,
+            topology: matches.get_topology(settings.topology)?,
+            output,
+        })
+    }
+}
+
+pub struct ConvertConfig {
+    pub input: String,
+    pub output: String,
+    /// Overrides the format picked from the output's extension
+    pub format: Option<Format>,
+    pub rule: Rule,
+}
+
+impl ConvertConfig {
+    fn from_matches(mut matches: Matches, settings: &Settings) -> Result<Self, String> {
+        Ok(Self {
+            format: matches.get("format")?,
+            rule: matches.get_rule(settings.rule.clone())?,
+            output: matches.positional.remove(1),
+            input: matches.positional.remove(0),
+        })
+    }
+}
+
+pub struct InfoConfig {
+    pub pattern: String,
+    pub generations: usize,
+    pub rule: Rule,
+    pub topology: Topology,
+}
+
+impl InfoConfig {
+    fn from_matches(mut matches: Matches, settings: &Settings) -> Result<Self, String> {
+        Ok(Self {
+            generations: matches.get("generations")?.unwrap_or(1000),
+            rule: matches.get_rule(settings.rule.clone())?,
+            topology: matches.get_topology(settings.topology)?,
+            pattern: matches.positional.remove(0),
+        })
+    }
+}
+
+pub struct BenchConfig {
+    pub board_width: usize,
+    pub board_height: usize,
+    pub load: Option<String>,
+    pub soup: Soup,
+    pub rule: Rule,
+    pub chance: Option<Chance>,
+    pub topology: Topology,
+    pub generations: usize,
+}
+
+impl BenchConfig {
+    fn from_matches(matches: &Matches, settings: &Settings) -> Result<Self, String> {
+        let rule = matches.get_rule(settings.rule.clone())?;
+        let chance = matches.get_chance(&rule)?;
+        Ok(Self {
+            board_width: matches.get("width")?.unwrap_or(256),
+            board_height: matches.get("height")?.unwrap_or(256),
+            load: matches.get("load")?,
+            soup: matches.get_soup()?.unwrap_or_else(|| Soup::new(None)),
+            rule,
+            chance,
+            topology: matches.get_topology(settings.topology)?,
+            generations: matches.get("generations")?.unwrap_or(1000),
+        })
+    }
+}
+
+pub struct ExportConfig {
+    pub pattern: String,
+    /// A `.png`, `.svg` or `.gif` file
+    pub output: String,
+    /// Generations drawn after the first, or `None` for the default of the format
+    pub generations: Option<usize>,
+    pub rule: Rule,
+    pub chance: Option<Chance>,
+    pub topology: Topology,
+    pub theme: Theme,
+    pub cell_size: usize,
+    pub grid: Option<[u8; 3]>,
+    /// Time each GIF frame is shown in milliseconds
+    pub delay: u64,
+    pub shape: Shape,
+    pub highlight: Option<Region>,
+    /// Draw arrows over spaceships in an SVG
+    pub arrows: bool,
+}
+
+impl ExportConfig {
+    fn from_matches(mut matches: Matches, settings: &Settings) -> Result<Self, String> {
+        let grid = match matches.get::<String>("grid-color")? {
+            Some(color) => {
+                let color = settings::parse_color(&color)?;
+                Some(image::rgb(color).ok_or_else(|| format!("{:?} can't be drawn in an image", color))?)
+            }
+            None if matches.has("grid") => Some([200, 200, 200]),
+            None => None,
+        };
+
+        let cell_size = matches.get("cell-size")?.unwrap_or(8);
+        if !(1..=64).contains(&cell_size) {
+            return Err(String::from("--cell-size must be between 1 and 64"));
+        }
+
+        let rule = matches.get_rule(settings.rule.clone())?;
+        let chance = matches.get_chance(&rule)?;
+        Ok(Self {
+            generations: matches.get("generations")?,
+            rule,
+            chance,
+            topology: matches.get_topology(settings.topology)?,
+            theme: matches.get_theme(settings.theme)?,
+            cell_size,
+            grid,
+            delay: matches.get("delay")?.unwrap_or(100),
+            shape: matches.get::<String>("shape")?.map(|s| s.parse()).transpose()?.unwrap_or_default(),
+            highlight: matches.get::<String>("highlight")?.map(|r| r.parse()).transpose()?,
+            arrows: matches.has("arrows"),
+            output: matches.positional.remove(1),
+            pattern: matches.positional.remove(0),
+        })
+    }
+}
+
+pub struct ReplayConfig {
+    pub journal: String,
+    /// Show the replay on the terminal rather than only writing the final board
+    pub visual: bool,
+    pub output: Option<String>,
+}
+
+impl ReplayConfig {
+    fn from_matches(mut matches: Matches) -> Result<Self, String> {
+        Ok(Self {
+            visual: matches.has("visual"),
+            output: matches.get("output")?,
+            journal: matches.positional.remove(0),
+        })
+    }
+}
+
+#[test]
+fn test_parse_flags() {
+    let args: Vec<String> = ["gol", "run", "-w", "20", "-H", "12", "--alive-char", "@", "--rule", "B36/S23", "--random"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+
+    match parse(&args, &Settings::default()).unwrap() {
+        Command::Run(config) => {
+            assert_eq!(config.board_width, 20);
+            assert_eq!(config.board_height, 12);
+            assert_eq!(config.alive_cell, '@');
+            assert_eq!(config.rule.to_string(), "B36/S23");
+            assert_eq!(config.soup.unwrap().density, 0.5);
+        }
+        _ => panic!("expected the run command"),
+    }
+
+    let args: Vec<String> = ["gol", "edit", "--seed", "7", "--symmetry", "D4", "--soup-size", "10x12"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+
+    match parse(&args, &Settings::default()).unwrap() {
+        Command::Edit(config) => {
+            let soup = config.soup.unwrap();
+            assert_eq!(soup.seed, 7);
+            assert_eq!(soup.region, Some((10, 12)));
+            assert_eq!(soup.symmetry.to_string(), "D4");
+        }
+        _ => panic!("expected the edit command"),
+    }
+
+    let args: Vec<String> = ["gol", "convert", "a.rle"].iter().map(|s| s.to_string()).collect();
+    assert!(parse(&args, &Settings::default()).is_err());
+
+    // -h is help, as with most tools, rather than the height
+    for args in [vec!["gol", "-h"], vec!["gol", "run", "-h"], vec!["gol", "export", "-h"]] {
+        let args: Vec<String> = args.iter().map(|s| s.to_string()).collect();
+        assert!(matches!(parse(&args, &Settings::default()).unwrap(), Command::Help(_)));
+    }
+}
@@ -0,0 +1,297 @@
+use crate::args::{BatchConfig, BenchConfig, ConvertConfig, ExportConfig, InfoConfig};
+use crate::life::formats::{self, Format};
+use crate::life::image::{self, Render};
+use crate::life::svg::{self, Overlay};
+use crate::life::rule::Rule;
+use crate::life::{Board, Cell, Life, Pos, Topology};
+use std::collections::VecDeque;
+use std::io::Write;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// How many previous generations are compared against when looking for a cycle
+const HISTORY_LEN: usize = 32;
+
+pub struct Stats {
+    pub generations: usize,
+    pub initial_population: usize,
+    pub population: usize,
+    /// Population of each team of a colored rule
+    pub teams: Vec<usize>,
+    /// Period of the cycle the board settled into, if one was detected
+    pub period: Option<usize>,
+    pub bounding_box: Option<(Pos, Pos)>,
+    pub elapsed: Duration,
+}
+
+impl Stats {
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = vec![
+            format!("generations: {}", self.generations),
+            format!("initial population: {}", self.initial_population),
+            format!("population: {}", self.population),
+        ];
+        for (team, population) in self.teams.iter().enumerate() {
+            lines.push(format!("population of team {}: {}", team + 1, population));
+        }
+        lines.push(
+            match self.period {
+                Some(1) => String::from("stable: yes (still life)"),
+                Some(p) => format!("stable: yes (period {})", p),
+                None => String::from("stable: no"),
+            },
+        );
+
+        if let Some((ul, lr)) = self.bounding_box {
+            lines.push(format!(
+                "bounding box: {}x{} at ({}, {})",
+                lr.x - ul.x + 1,
+                lr.y - ul.y + 1,
+                ul.x,
+                ul.y
+            ));
+        }
+        lines.push(format!("elapsed: {}ms", self.elapsed.as_millis()));
+
+        lines
+    }
+}
+
+fn load_life(pattern: &str, rule: Rule, topology: Topology) -> Result<Life, String> {
+    let board = formats::load(Path::new(pattern))?;
+    let mut life = Life::new((board.width(), board.height()), '.', 'O', None, Some(board));
+    life.rule = rule;
+    life.topology = topology;
+    life.save_state();
+    Ok(life)
+}
+
+pub fn run(config: BatchConfig) -> Result<(), String> {
+    let mut life = load_life(&config.pattern, config.rule, config.topology)?;
+    life.chance = config.chance;
+
+    let stats = simulate(&mut life, config.generations, config.until_stable);
+    let mut comments = stats.lines();
+    if let Some(chance) = &life.chance {
+        comments.push(format!("chances: {}", chance));
+    }
+
+    match (&config.output, config.format) {
+        (Some(path), format) => {
+            formats::save(Path::new(path), &life.board, &life.rule, format, &comments)?;
+            if format == Format::Life {
+                for line in &comments {
+                    println!("{}", line);
+                }
+            }
+        }
+        (None, Format::Cells) => write_stdout(&formats::write_cells(&life.board, &comments)?)?,
+        (None, Format::Rle) => write_stdout(&formats::write_rle(&life.board, &life.rule, &comments))?,
+        (None, Format::Life) => unreachable!("rejected when parsing the batch config"),
+    }
+
+    Ok(())
+}
+
+pub fn convert(config: ConvertConfig) -> Result<(), String> {
+    let board = formats::load(Path::new(&config.input))?;
+    let output = Path::new(&config.output);
+    let format = match config.format {
+        Some(format) => format,
+        None => Format::from_path(output)
+            .ok_or_else(|| format!("Can't tell the format of {}, pass --format", output.display()))?,
+    };
+
+    formats::save(output, &board, &config.rule, format, &[])
+}
+
+pub fn info(config: InfoConfig) -> Result<(), String> {
+    let mut life = load_life(&config.pattern, config.rule, config.topology)?;
+    let (width, height) = life.dims();
+
+    println!("pattern: {}", config.pattern);
+    println!("size: {}x{}", width, height);
+    println!("rule: {}", life.rule);
+
+    let stats = simulate(&mut life, config.generations, true);
+    println!("population: {}", stats.initial_population);
+    match stats.period {
+        Some(_) if stats.population == 0 => println!("fate: dies out after {} generations", stats.generations),
+        Some(1) => println!("fate: still life after {} generations", stats.generations - 1),
+        Some(p) => println!("fate: period {} oscillator after {} generations", p, stats.generations - p),
+        None => println!("fate: unsettled after {} generations", stats.generations),
+    }
+
+    Ok(())
+}
+
+pub fn bench(config: BenchConfig) -> Result<(), String> {
+    let mut life = match &config.load {
+        Some(pattern) => load_life(pattern, config.rule, config.topology)?,
+        None => {
+            let mut life = Life::new(
+                (config.board_width, config.board_height),
+                '.',
+                'O',
+                Some(config.soup),
+                None,
+            );
+            life.rule = config.rule;
+            life.topology = config.topology;
+            life.refill_soup();
+            life.save_state();
+            life
+        }
+    };
+    life.chance = config.chance;
+
+    let (width, height) = life.dims();
+    let stats = simulate(&mut life, config.generations, false);
+    let secs = stats.elapsed.as_secs_f64();
+
+    println!("board: {}x{}, rule: {}", width, height, life.rule);
+    if config.load.is_none() {
+        println!("soup: {}", config.soup);
+    }
+    if let Some(chance) = &life.chance {
+        println!("chances: {}", chance);
+    }
+    println!("{} generations in {:.3}s", stats.generations, secs);
+    println!("{:.1} generations/s", stats.generations as f64 / secs);
+    println!("{:.1} million cells/s", (stats.generations * width * height) as f64 / secs / 1e6);
+
+    Ok(())
+}
+
+/// Draws the pattern as one PNG or SVG, as numbered frames `name-0000.png`
+/// onwards when generations are given, or as an animated GIF
+pub fn export(config: ExportConfig) -> Result<(), String> {
+    let mut life = load_life(&config.pattern, config.rule, config.topology)?;
+    life.chance = config.chance;
+    let render = Render::new(&life.rule, &config.theme, config.cell_size, config.grid);
+
+    let output = Path::new(&config.output);
+    let extension = output.extension().and_then(|e| e.to_str()).map(str::to_ascii_lowercase);
+    let generations = match (extension.as_deref(), config.generations) {
+        (Some("png" | "svg" | "gif"), Some(generations)) => generations,
+        (Some("png" | "svg"), None) => 0,
+        (Some("gif"), None) => 100,
+        _ => return Err(format!("Can't tell the image format of {}, expected .png, .svg or .gif", output.display())),
+    };
+
+    let mut frames = vec![life.board.clone()];
+    for _ in 0..generations {
+        life.tick();
+        frames.push(life.board.clone());
+    }
+
+    let save = |path: &Path, board: &Board| match extension.as_deref() {
+        Some("svg") => {
+            let overlay = Overlay {
+                shape: config.shape,
+                highlight: config.highlight,
+                arrows: if config.arrows { svg::spaceships(board, &life.rule) } else { Vec::new() },
+            };
+            svg::save_svg(path, board, &render, &overlay)
+        }
+        _ => image::save_png(path, board, &render),
+    };
+
+    if extension.as_deref() == Some("gif") {
+        let delay = (config.delay / 10).clamp(1, u16::MAX as u64) as u16;
+        return image::save_gif(output, &frames, &render, delay);
+    }
+    if let [board] = &frames[..] {
+        return save(output, board);
+    }
+
+    let (stem, extension) = (output.with_extension(""), extension.as_deref().unwrap_or_default());
+    for (generation, board) in frames.iter().enumerate() {
+        let path = format!("{}-{:04}.{}", stem.display(), generation, extension);
+        save(Path::new(&path), board)?;
+    }
+    println!("wrote {} frames to {}-*.{}", frames.len(), stem.display(), extension);
+
+    Ok(())
+}
+
+/// Runs `life` for up to `generations` ticks. If `until_stable` is set the run
+/// stops as soon as the board repeats one of its recent states
+pub fn simulate(life: &mut Life, generations: usize, until_stable: bool) -> Stats {
+    let start = Instant::now();
+    let initial_population = life.population();
+    let mut history: VecDeque<Vec<Cell>> = VecDeque::with_capacity(HISTORY_LEN);
+    let mut period = None;
+    let mut ran = 0;
+
+    history.push_front(life.board.data().to_vec());
+    while ran < generations {
+        life.tick();
+        ran += 1;
+
+        period = history
+            .iter()
+            .position(|prev| prev[..] == *life.board.data())
+            .map(|i| i + 1);
+
+        if period.is_some() && until_stable {
+            break;
+        }
+
+        if history.len() == HISTORY_LEN {
+            history.pop_back();
+        }
+        history.push_front(life.board.data().to_vec());
+    }
+
+    Stats {
+        generations: ran,
+        initial_population,
+        population: life.population(),
+        teams: life.team_populations(),
+        period,
+        bounding_box: bounding_box(&life.board, life.rule.teams()),
+        elapsed: start.elapsed(),
+    }
+}
+
+fn bounding_box(board: &Board, teams: u8) -> Option<(Pos, Pos)> {
+    let mut bounds: Option<(Pos, Pos)> = None;
+
+    for ([x, y], cell) in board {
+        // every team of a colored rule is alive
+        if *cell != Cell::ALIVE && (*cell == Cell::DEAD || cell.0 > teams) {
+            continue;
+        }
+
+        bounds = Some(match bounds {
+            Some((ul, lr)) => (
+                Pos { x: ul.x.min(x), y: ul.y.min(y) },
+                Pos { x: lr.x.max(x), y: lr.y.max(y) },
+            ),
+            None => (Pos { x, y }, Pos { x, y }),
+        });
+    }
+
+    bounds
+}
+
+fn write_stdout(s: &str) -> Result<(), String> {
+    std::io::stdout()
+        .write_all(s.as_bytes())
+        .map_err(|e| format!("Failed to write to stdout: {}", e))
+}
+
+#[test]
+fn test_blinker_is_stable() {
+    let mut board = Board::new([5, 5], Cell::DEAD);
+    for y in 1..4 {
+        board[[2, y]] = Cell::ALIVE;
+    }
+
+    let mut life = Life::new((5, 5), '.', 'O', None, Some(board));
+    let stats = simulate(&mut life, 100, true);
+    assert_eq!(stats.period, Some(2));
+    assert_eq!(stats.generations, 2);
+    assert_eq!(stats.population, 3);
+}
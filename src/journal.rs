@@ -0,0 +1,609 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::mpsc::{RecvTimeoutError, TryRecvError};
+use std::time::Duration;
+
+use crate::life::chance::Chance;
+use crate::life::formats;
+use crate::life::lenia::{self, Field};
+use crate::life::rule::Rule;
+use crate::life::{Board, Life, Topology};
+use crate::settings::{Action, Bindings, KeyBindings};
+use crate::term::{Keys, TermEvent};
+
+/// An input handled by the UI
+#[derive(Clone, Debug, PartialEq)]
+pub enum Event {
+    Edit(Action),
+    /// The orientation of a prefab being placed, or cancelling it
+    Prefab(Action),
+    /// A run action, handled after this many generations of the run
+    Run(usize, Action),
+    /// An answer typed at a prompt, or `None` if it was cancelled
+    Text(Option<String>),
+    /// A seed drawn for something random, such as chances set during a run
+    Seed(u64),
+}
+
+/// The name of `action` in `bindings`, or `resize WxH` for a resize
+fn action_name(bindings: &Bindings, action: Action) -> String {
+    match action {
+        Action::Resize(columns, rows) => format!("resize {}x{}", columns, rows),
+        action => bindings.name(action).unwrap_or("?").to_string(),
+    }
+}
+
+fn named_action(bindings: &Bindings, name: &str) -> Result<Action, String> {
+    if let Some(size) = name.strip_prefix("resize ") {
+        let size = size.split_once('x').and_then(|(columns, rows)| Some((columns.parse().ok()?, rows.parse().ok()?)));
+        return size.map(|(columns, rows)| Action::Resize(columns, rows)).ok_or_else(|| format!("bad size `{}`", name));
+    }
+    bindings.named(name).ok_or_else(|| format!("unknown action `{}`", name))
+}
+
+impl Event {
+    fn write(&self, keys: &KeyBindings) -> String {
+        match self {
+            Event::Edit(action) => format!("edit {}", action_name(&keys.editor, *action)),
+            Event::Prefab(action) => format!("prefab {}", action_name(&keys.prefab, *action)),
+            Event::Run(generation, action) => format!("run {} {}", generation, action_name(&keys.run, *action)),
+            Event::Text(Some(text)) => format!("text {}", text),
+            Event::Text(None) => String::from("cancel"),
+            Event::Seed(seed) => format!("seed {}", seed),
+        }
+    }
+
+    fn read(line: &str, keys: &KeyBindings) -> Result<Self, String> {
+        let (kind, rest) = line.split_once(' ').unwrap_or((line, ""));
+
+        match kind {
+            "edit" => Ok(Event::Edit(named_action(&keys.editor, rest)?)),
+            "prefab" => Ok(Event::Prefab(named_action(&keys.prefab, rest)?)),
+            "run" => {
+                let (generation, name) = rest.split_once(' ').ok_or("expected `run GENERATION ACTION`")?;
+                let generation = generation.parse().map_err(|_| format!("bad generation `{}`", generation))?;
+                Ok(Event::Run(generation, named_action(&keys.run, name)?))
+            }
+            "text" => Ok(Event::Text(Some(rest.to_string()))),
+            "cancel" => Ok(Event::Text(None)),
+            "seed" => Ok(Event::Seed(rest.parse().map_err(|_| format!("bad seed `{}`", rest))?)),
+            _ => Err(format!("unknown event `{}`", line)),
+        }
+    }
+}
+
+/// A session as it started, and every input handled during it
+pub struct Journal {
+    pub rule: Rule,
+    pub topology: Topology,
+    pub chance: Option<Chance>,
+    /// Delay between ticks in milliseconds
+    pub delay: u64,
+    /// Whether the session started running rather than in the editor
+    pub start_running: bool,
+    /// Whether the board filled the terminal across and down, and so followed its resizes
+    pub fill: (bool, bool),
+    pub board: Board,
+    /// The exact values of a Lenia board, which `board` only has quantized
+    pub field: Option<Field>,
+    pub events: Vec<Event>,
+    /// The board the session ended with, missing if it never ended cleanly
+    pub last: Option<Board>,
+}
+
+/// How long the editor and a paused run wait for a key before getting on with
+/// anything else, such as autosaving
+const IDLE: Duration = Duration::from_millis(250);
+
+/// First line of every journal
+const HEADER: &str = "#journal 1";
+
+impl Journal {
+    /// Reads a journal written by `Writer`, naming actions as in `keys`
+    pub fn load(path: &Path, keys: &KeyBindings) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let err = |line: usize, e: &str| format!("Invalid journal {} on line {}: {}", path.display(), line + 1, e);
+
+        let mut lines = text.lines().enumerate();
+        if lines.next().map(|(_, line)| line) != Some(HEADER) {
+            return Err(err(0, "not a journal"));
+        }
+
+        // the boards are RLE, ending with `!`
+        let read_board = |lines: &mut dyn Iterator<Item = (usize, &str)>| -> Result<Board, String> {
+            let mut rle = String::new();
+            for (i, line) in lines {
+                rle.push_str(line);
+                rle.push('\n');
+                if line.ends_with('!') {
+                    return formats::read_rle(&rle).map_err(|e| err(i, &e));
+                }
+            }
+            Err(err(text.lines().count(), "unfinished board"))
+        };
+
+        let (mut rule, mut topology, mut chance, mut delay) = (None, Topology::Bounded, None, 64);
+        let (mut start_running, mut fill, mut board, mut events, mut last) = (false, (false, false), None, Vec::new(), None);
+        let mut field = None;
+        // a rule table is written a line at a time
+        let mut table: Option<String> = None;
+        while let Some((i, line)) = lines.next() {
+            let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+            match key {
+                "rule" => rule = Some(value.parse().map_err(|e: String| err(i, &e))?),
+                "table" => table.get_or_insert_with(String::new).push_str(&format!("{}\n", value)),
+                "topology" => topology = value.parse().map_err(|e: String| err(i, &e))?,
+                "chances" => chance = Some(value.parse().map_err(|e: String| err(i, &e))?),
+                "delay" => delay = value.parse().map_err(|_| err(i, "bad delay"))?,
+                "start" => start_running = value == "run",
+                "fill" => fill = (value.contains("width"), value.contains("height")),
+                "board" => board = Some(read_board(&mut lines)?),
+                // a row of values per line, as many rows as the board has
+                "field" => {
+                    let size = board.as_ref().map(|board: &Board| (board.width(), board.height()));
+                    let (width, height) = size.ok_or_else(|| err(i, "field before the board"))?;
+                    let rows: Vec<&str> = lines.by_ref().take(height).map(|(_, line)| line).collect();
+                    field = Some(lenia::read_values(&rows.join("\n"), (width, height)).map_err(|e| err(i, &e))?);
+                }
+                "end" => last = Some(read_board(&mut lines)?),
+                _ => events.push(Event::read(line, keys).map_err(|e| err(i, &e))?),
+            }
+        }
+        if let Some(table) = table {
+            rule = Some(Rule::from_table(&table).map_err(|e| err(1, &e))?);
+        }
+
+        Ok(Journal {
+            rule: rule.ok_or_else(|| err(1, "missing rule"))?,
+            topology,
+            chance,
+            delay,
+            start_running,
+            fill,
+            board: board.ok_or_else(|| err(1, "missing board"))?,
+            field,
+            events,
+            last,
+        })
+    }
+}
+
+/// Writes a journal as the session goes, so it is complete up to the last input even after a crash
+pub struct Writer {
+    file: BufWriter<File>,
+    /// Names actions are written with
+    keys: KeyBindings,
+}
+
+impl Writer {
+    /// Starts a journal of a session beginning with `life`, with a board
+    /// filling the terminal across and down as given by `fill`
+    pub fn create(
+        path: &Path,
+        life: &Life,
+        start_running: bool,
+        fill: (bool, bool),
+        delay: u64,
+        keys: &KeyBindings,
+    ) -> Result<Self, String> {
+        let file = File::create(path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+        let mut writer = Writer { file: BufWriter::new(file), keys: keys.clone() };
+
+        let mut header = format!("{}\n{}topology {}\n", HEADER, life.rule.to_lines(), life.topology);
+        if let Some(chance) = &life.chance {
+            header.push_str(&format!("chances {}\n", chance));
+        }
+        header.push_str(&format!("delay {}\nstart {}\n", delay, if start_running { "run" } else { "edit" }));
+        if fill != (false, false) {
+            let across = if fill.0 { " width" } else { "" };
+            let down = if fill.1 { " height" } else { "" };
+            header.push_str(&format!("fill{}{}\n", across, down));
+        }
+        header.push_str(&format!("board\n{}", formats::write_rle(&life.board, &life.rule, &[])));
+        if let Rule::Lenia(_) = life.rule {
+            header.push_str(&format!("field\n{}", lenia::write_values(&life.field())));
+        }
+        writer.write(&header);
+
+        Ok(writer)
+    }
+
+    fn event(&mut self, event: Event) {
+        let line = event.write(&self.keys) + "\n";
+        self.write(&line);
+    }
+
+    /// Ends the journal with the board the session ended with
+    pub fn finish(mut self, life: &Life) {
+        let end = format!("end\n{}", formats::write_rle(&life.board, &life.rule, &[]));
+        self.write(&end);
+    }
+
+    /// A journal that can't be written is given up on rather than stopping the session
+    fn write(&mut self, s: &str) {
+        let _ = self.file.write_all(s.as_bytes()).and_then(|_| self.file.flush());
+    }
+}
+
+/// The action of a key in `bindings`, or a resize
+fn action(bindings: &Bindings, event: TermEvent) -> Option<Action> {
+    match event {
+        TermEvent::Key(code) => bindings.action(code),
+        TermEvent::Resize(columns, rows) => Some(Action::Resize(columns, rows)),
+    }
+}
+
+/// Where the UI takes its actions and typed answers from
+pub enum Input {
+    /// Keys pressed on the terminal, written to a journal if one is kept
+    Keys(Keys, Option<Writer>),
+    /// A journal played back, followed by the keys when it is shown on the
+    /// terminal, or by quitting when it isn't
+    Replay(VecDeque<Event>, Option<Keys>),
+    /// A replay stopped because the session asked for something other than
+    /// the next event of the journal, during a run at this generation or else
+    /// in the editor
+    Diverged(Option<usize>),
+}
+
+impl Input {
+    /// Whether inputs come from a journal with nothing drawn
+    pub fn headless(&self) -> bool {
+        matches!(self, Input::Replay(_, None))
+    }
+
+    /// Whether inputs come from a journal. Replays don't write any files
+    pub fn replaying(&self) -> bool {
+        matches!(self, Input::Replay(..) | Input::Diverged(_))
+    }
+
+    /// Why a replay stopped before the end of its journal, if it did
+    pub fn diverged(&self) -> Option<String> {
+        match self {
+            Input::Diverged(Some(generation)) => Some(format!("replay diverged at generation {}", generation)),
+            Input::Diverged(None) => Some(String::from("replay diverged in the editor")),
+            _ => None,
+        }
+    }
+
+    /// Stops a replay, whose journal the session no longer follows
+    fn diverge(&mut self, generation: Option<usize>) {
+        if let Input::Replay(..) = self {
+            *self = Input::Diverged(generation);
+        }
+    }
+
+    /// The next editor action, waiting for it if needed. `None` for keys
+    /// without an action, and when none came for a moment
+    pub fn editor(&mut self, keys: &KeyBindings) -> Option<Action> {
+        match self.next(|event| matches!(event, Event::Edit(_))) {
+            Some(Event::Edit(action)) => Some(action),
+            _ => match self {
+                Input::Keys(rx, journal) => {
+                    // the keys stop when the session is interrupted
+                    let action = match rx.recv_timeout(IDLE) {
+                        Ok(event) => action(&keys.editor, event)?,
+                        Err(RecvTimeoutError::Timeout) => return None,
+                        Err(RecvTimeoutError::Disconnected) => Action::Quit,
+                    };
+                    if let Some(journal) = journal {
+                        journal.event(Event::Edit(action));
+                    }
+                    Some(action)
+                }
+                Input::Replay(events, _) if events.is_empty() => Some(Action::Quit),
+                _ => {
+                    self.diverge(None);
+                    Some(Action::Quit)
+                }
+            },
+        }
+    }
+
+    /// The orientation of a prefab, or `Action::Cancel`
+    pub fn prefab(&mut self, keys: &KeyBindings) -> Option<Action> {
+        match self.next(|event| matches!(event, Event::Prefab(_))) {
+            Some(Event::Prefab(action)) => Some(action),
+            _ => match self {
+                Input::Keys(rx, journal) => {
+                    let action = match rx.recv() {
+                        Ok(event) => action(&keys.prefab, event)?,
+                        Err(_) => Action::Cancel,
+                    };
+                    if let Some(journal) = journal {
+                        journal.event(Event::Prefab(action));
+                    }
+                    Some(action)
+                }
+                Input::Replay(events, _) if events.is_empty() => Some(Action::Cancel),
+                _ => {
+                    self.diverge(None);
+                    Some(Action::Cancel)
+                }
+            },
+        }
+    }
+
+    /// The run actions to handle after `generation` generations, waiting a
+    /// moment for one while `paused`
+    pub fn run(&mut self, keys: &KeyBindings, generation: usize, paused: bool) -> Vec<Action> {
+        let mut actions = Vec::new();
+        while let Some(Event::Run(_, action)) = self.next(|event| matches!(event, Event::Run(g, _) if *g <= generation)) {
+            actions.push(action);
+        }
+
+        match self {
+            Input::Keys(rx, journal) => {
+                // while paused, wait for a key rather than spinning
+                let (mut codes, mut closed) = (Vec::new(), false);
+                if paused {
+                    match rx.recv_timeout(IDLE) {
+                        Ok(code) => codes.push(code),
+                        Err(RecvTimeoutError::Timeout) => {}
+                        Err(RecvTimeoutError::Disconnected) => closed = true,
+                    }
+                }
+                loop {
+                    match rx.try_recv() {
+                        Ok(code) => codes.push(code),
+                        Err(TryRecvError::Empty) => break,
+                        Err(TryRecvError::Disconnected) => {
+                            closed = true;
+                            break;
+                        }
+                    }
+                }
+
+                // the keys stop when the session is interrupted
+                let mut run_actions: Vec<Action> = codes.into_iter().filter_map(|event| action(&keys.run, event)).collect();
+                if closed {
+                    run_actions.push(Action::Quit);
+                }
+                for action in run_actions {
+                    if let Some(journal) = journal {
+                        journal.event(Event::Run(generation, action));
+                    }
+                    actions.push(action);
+                }
+            }
+            // only a replay without a terminal is left once the journal runs out
+            Input::Replay(events, _) if events.is_empty() => actions.push(Action::Quit),
+            // a paused replay with nothing left for this generation would wait forever
+            Input::Replay(..) if paused && actions.is_empty() => {
+                self.diverge(Some(generation));
+                actions.push(Action::Quit);
+            }
+            Input::Replay(..) => {}
+            Input::Diverged(_) => actions.push(Action::Quit),
+        }
+
+        actions
+    }
+
+    /// An answer typed at a prompt, read from the terminal's keys by `read`
+    /// when they are the input. `None` if it was cancelled
+    pub fn text(&mut self, read: impl FnOnce(&mut Keys) -> Option<String>) -> Option<String> {
+        match self.next(|event| matches!(event, Event::Text(_))) {
+            Some(Event::Text(text)) => text,
+            _ => match self {
+                Input::Keys(rx, journal) => {
+                    let text = read(rx);
+                    if let Some(journal) = journal {
+                        journal.event(Event::Text(text.clone()));
+                    }
+                    text
+                }
+                Input::Replay(events, _) if events.is_empty() => None,
+                _ => {
+                    self.diverge(None);
+                    None
+                }
+            },
+        }
+    }
+
+    /// A new random seed, or the one drawn when the journal was written
+    pub fn seed(&mut self) -> u64 {
+        match self.next(|event| matches!(event, Event::Seed(_))) {
+            Some(Event::Seed(seed)) => seed,
+            _ => {
+                let seed = rand::random();
+                match self {
+                    Input::Keys(_, Some(journal)) => journal.event(Event::Seed(seed)),
+                    Input::Replay(events, _) if !events.is_empty() => self.diverge(None),
+                    _ => {}
+                }
+                seed
+            }
+        }
+    }
+
+    /// Ends the journal being written, if any
+    pub fn finish(&mut self, life: &Life) {
+        if let Input::Keys(_, journal) = self {
+            if let Some(journal) = journal.take() {
+                journal.finish(life);
+            }
+        }
+    }
+
+    /// The next event of a replay if `wanted` accepts it. An event that isn't
+    /// wanted is left for later, and when the replay has run out the terminal's
+    /// keys take over if there are any
+    fn next(&mut self, wanted: impl Fn(&Event) -> bool) -> Option<Event> {
+        let Input::Replay(events, keys) = self else {
+            return None;
+        };
+
+        match events.front() {
+            Some(event) if wanted(event) => events.pop_front(),
+            Some(_) => None,
+            None => {
+                if let Some(rx) = keys.take() {
+                    *self = Input::Keys(rx, None);
+                }
+                None
+            }
+        }
+    }
+}
+
+#[test]
+fn test_journal() {
+    use crate::life::Cell;
+    use crate::settings::Settings;
+    use crossterm::event::KeyCode;
+    use std::sync::mpsc;
+
+    let keys = Settings::default().keys;
+    let path = std::env::temp_dir().join(format!("game_of_life_test_{}.journal", std::process::id()));
+
+    let mut board = Board::new([6, 5], Cell::DEAD);
+    board[[1, 2]] = Cell::ALIVE;
+    let mut life = Life::new((6, 5), ' ', '#', None, Some(board));
+    life.chance = Some("b3=0.5,seed=7".parse().unwrap());
+
+    // what is pressed is written as it is handled
+    let (tx, rx) = mpsc::channel();
+    let writer = Writer::create(&path, &life, false, (false, true), 32, &keys).unwrap();
+    let mut input = Input::Keys(Keys::new(rx), Some(writer));
+    for code in [KeyCode::Char('x'), KeyCode::Char(' '), KeyCode::Enter] {
+        tx.send(TermEvent::Key(code)).unwrap();
+    }
+    assert_eq!(input.editor(&keys), None);
+    assert_eq!(input.editor(&keys), Some(Action::Toggle));
+    assert_eq!(input.editor(&keys), Some(Action::Start));
+    tx.send(TermEvent::Key(KeyCode::Char('p'))).unwrap();
+    tx.send(TermEvent::Resize(100, 40)).unwrap();
+    assert_eq!(input.run(&keys, 12, false), vec![Action::Pause, Action::Resize(100, 40)]);
+    // a resize while a line is typed is handled after it
+    tx.send(TermEvent::Resize(90, 30)).unwrap();
+    tx.send(TermEvent::Key(KeyCode::Enter)).unwrap();
+    let typed = input.text(|keys| keys.key().map(|_| String::from("b2=0.1")));
+    assert_eq!(typed.as_deref(), Some("b2=0.1"));
+    assert_eq!(input.run(&keys, 12, false), vec![Action::Resize(90, 30)]);
+    assert_eq!(input.text(|_| None), None);
+    let seed = input.seed();
+    input.finish(&life);
+
+    // the keys stopping, as they do on Ctrl-C, quits
+    drop(tx);
+    assert_eq!(input.editor(&keys), Some(Action::Quit));
+    assert_eq!(input.run(&keys, 13, true), vec![Action::Quit]);
+
+    let journal = Journal::load(&path, &keys).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(journal.rule.to_string(), "B3/S23");
+    assert_eq!(journal.chance.unwrap().to_string(), "b3=0.5,seed=7");
+    assert_eq!((journal.delay, journal.start_running, journal.fill), (32, false, (false, true)));
+    assert!(journal.board.data() == life.board.data());
+    assert!(journal.last.unwrap().data() == life.board.data());
+    let events = vec![
+        Event::Edit(Action::Toggle),
+        Event::Edit(Action::Start),
+        Event::Run(12, Action::Pause),
+        Event::Run(12, Action::Resize(100, 40)),
+        Event::Text(Some(String::from("b2=0.1"))),
+        Event::Run(12, Action::Resize(90, 30)),
+        Event::Text(None),
+        Event::Seed(seed),
+    ];
+    assert_eq!(journal.events, events);
+
+    // played back, run actions wait for their generation and the end quits
+    let mut replay = Input::Replay(events.into(), None);
+    assert_eq!(replay.editor(&keys), Some(Action::Toggle));
+    assert_eq!(replay.editor(&keys), Some(Action::Start));
+    assert_eq!(replay.run(&keys, 11, false), vec![]);
+    assert_eq!(replay.run(&keys, 12, false), vec![Action::Pause, Action::Resize(100, 40)]);
+    assert_eq!(replay.text(|_| None).as_deref(), Some("b2=0.1"));
+    assert_eq!(replay.run(&keys, 12, false), vec![Action::Resize(90, 30)]);
+    assert_eq!(replay.text(|_| Some(String::new())), None);
+    assert_eq!(replay.seed(), seed);
+    assert_eq!(replay.editor(&keys), Some(Action::Quit));
+    assert_eq!(replay.run(&keys, 13, true), vec![Action::Quit]);
+    assert_eq!(replay.diverged(), None);
+
+    // a replay the session no longer follows stops rather than skipping ahead
+    let mut replay = Input::Replay(vec![Event::Run(20, Action::Pause)].into(), None);
+    assert_eq!(replay.run(&keys, 5, true), vec![Action::Quit]);
+    assert_eq!(replay.diverged().as_deref(), Some("replay diverged at generation 5"));
+    let mut replay = Input::Replay(vec![Event::Text(None)].into(), None);
+    assert_eq!(replay.editor(&keys), Some(Action::Quit));
+    assert_eq!(replay.diverged().as_deref(), Some("replay diverged in the editor"));
+}
+
+#[test]
+fn test_journal_table_rule() {
+    use crate::life::table::WIREWORLD;
+    use crate::life::Cell;
+    use crate::settings::Settings;
+
+    let keys = Settings::default().keys;
+    let dir = std::env::temp_dir();
+    let rule_path = dir.join(format!("game_of_life_test_{}.rule", std::process::id()));
+    let path = dir.join(format!("game_of_life_test_table_{}.journal", std::process::id()));
+    std::fs::write(&rule_path, WIREWORLD).unwrap();
+
+    // a wire with an electron on it
+    let mut board = Board::new([6, 3], Cell::DEAD);
+    for x in 0..6 {
+        board[[x, 1]] = Cell(3);
+    }
+    board[[1, 1]] = Cell(1);
+    board[[0, 1]] = Cell(2);
+    let mut life = Life::new((6, 3), ' ', '#', None, Some(board));
+    life.rule = Rule::load(&rule_path).unwrap();
+    std::fs::remove_file(&rule_path).unwrap();
+
+    Writer::create(&path, &life, true, (false, false), 32, &keys).unwrap().finish(&life);
+    let journal = Journal::load(&path, &keys).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(journal.rule.to_string(), "WireWorld");
+
+    // the replayed rule steps the board the same way
+    let mut replayed = Life::new((6, 3), ' ', '#', None, Some(journal.board));
+    replayed.rule = journal.rule;
+    for _ in 0..4 {
+        life.tick();
+        replayed.tick();
+        assert!(replayed.board.data() == life.board.data());
+    }
+    assert_eq!(life.board[[5, 1]], Cell(1));
+}
+
+#[test]
+fn test_journal_lenia() {
+    use crate::settings::Settings;
+    use std::rc::Rc;
+
+    let keys = Settings::default().keys;
+    let path = std::env::temp_dir().join(format!("game_of_life_test_lenia_{}.journal", std::process::id()));
+
+    let mut field = Field::new([16, 16], 0.0);
+    for (i, ([x, y], value)) in (&mut field).into_iter().enumerate() {
+        if (4..12).contains(&x) && (4..12).contains(&y) {
+            *value = (i % 7) as f32 / 7.0 + 0.013;
+        }
+    }
+    let mut life = Life::new((16, 16), ' ', '#', None, None);
+    life.rule = Rule::Lenia(Rc::new("lenia:R=3".parse().unwrap()));
+    life.set_field(field.clone());
+
+    Writer::create(&path, &life, true, (false, false), 32, &keys).unwrap();
+    let journal = Journal::load(&path, &keys).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert!(journal.field.as_ref().unwrap().data() == field.data());
+
+    // replayed from its exact values, the board runs the same
+    let mut replayed = Life::new((16, 16), ' ', '#', None, Some(journal.board));
+    replayed.rule = journal.rule;
+    replayed.set_field(journal.field.unwrap());
+    for _ in 0..5 {
+        life.tick();
+        replayed.tick();
+    }
+    assert!(replayed.field().data() == life.field().data());
+}
@@ -0,0 +1,204 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::fmt::Display;
+use std::str::FromStr;
+
+use super::rule::Rule;
+use super::{Board, Cell};
+
+/// Randomness added to the tick: chances of births and survivals by the number
+/// of alive neighbors, overriding what a life-like rule says for those counts,
+/// and noise flipping random cells. The same seed always gives the same run
+#[derive(Clone, Debug)]
+pub struct Chance {
+    pub seed: u64,
+    /// Chance of a dead cell with this many alive neighbors being born
+    pub birth: [Option<f64>; 9],
+    /// Chance of an alive cell with this many alive neighbors surviving
+    pub survival: [Option<f64>; 9],
+    /// Chance of each cell flipping between dead and alive every tick
+    pub noise: f64,
+    rng: StdRng,
+}
+
+impl Chance {
+    /// No chances and no noise, with a random seed if none is given
+    pub fn new(seed: Option<u64>) -> Self {
+        let seed = seed.unwrap_or_else(rand::random);
+        Chance { seed, birth: [None; 9], survival: [None; 9], noise: 0.0, rng: StdRng::seed_from_u64(seed) }
+    }
+
+    /// Starts the random numbers over, so the run repeats from here
+    pub fn reseed(&mut self) {
+        self.rng = StdRng::seed_from_u64(self.seed);
+    }
+
+    /// Where the random numbers are up to, to go back to with `rewind`
+    pub fn position(&self) -> StdRng {
+        self.rng.clone()
+    }
+
+    /// Carries on the random numbers from an earlier `position`
+    pub fn rewind(&mut self, position: StdRng) {
+        self.rng = position;
+    }
+
+    /// Applies settings such as `b3=0.9,s23=0.5,noise=0.01,seed=7` on top of the
+    /// current ones. `b` and `s` are followed by the neighbor counts they set,
+    /// and a chance of `-` goes back to the rule for those counts
+    pub fn apply(&mut self, spec: &str) -> Result<(), String> {
+        let err = |e: &str| format!("Invalid chances `{}`: {}", spec, e);
+
+        for setting in spec.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            let (key, value) = setting.split_once('=').ok_or_else(|| err("expected `name=value`"))?;
+            let (key, value) = (key.trim().to_ascii_lowercase(), value.trim());
+            let chance = || -> Result<Option<f64>, String> {
+                if value == "-" {
+                    return Ok(None);
+                }
+                match value.parse::<f64>() {
+                    Ok(p) if (0.0..=1.0).contains(&p) => Ok(Some(p)),
+                    _ => Err(err(&format!("`{}` isn't a chance between 0 and 1", value))),
+                }
+            };
+
+            match key.as_str() {
+                "noise" => self.noise = chance()?.unwrap_or(0.0),
+                "seed" => {
+                    self.seed = value.parse().map_err(|_| err(&format!("bad seed `{}`", value)))?;
+                    self.reseed();
+                }
+                _ => {
+                    let (table, counts) = match key.split_at(1) {
+                        ("b", counts) => (&mut self.birth, counts),
+                        ("s", counts) => (&mut self.survival, counts),
+                        _ => return Err(err(&format!("unknown setting `{}`", key))),
+                    };
+                    if counts.is_empty() {
+                        return Err(err(&format!("`{}` needs neighbor counts, such as {}3", key, key)));
+                    }
+
+                    let chance = chance()?;
+                    for count in counts.chars() {
+                        let count = count.to_digit(10).filter(|&c| c <= 8).ok_or_else(|| err("counts are 0 to 8"))?;
+                        table[count as usize] = chance;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// An error if there are chances by neighbor count and `rule` isn't
+    /// life-like, as no other rule counts its neighbors the way they do.
+    /// Noise works with any rule
+    pub fn check(&self, rule: &Rule) -> Result<(), String> {
+        let counts = self.birth.iter().chain(&self.survival).any(Option::is_some);
+        match (counts, rule) {
+            (false, _) | (true, Rule::LifeLike(_)) => Ok(()),
+            (true, rule) => Err(format!("Birth and survival chances only work with life-like rules, not {}", rule)),
+        }
+    }
+
+    /// The state a cell in `cell` with `count` alive neighbors moves to, where the
+    /// rule says `next`. A cell that fails to survive starts dying if the rule has
+    /// dying states
+    pub fn decide(&mut self, cell: Cell, count: usize, next: Cell, states: u8) -> Cell {
+        match (cell, self.birth[count], self.survival[count]) {
+            (Cell::DEAD, Some(p), _) if self.rng.gen_bool(p) => Cell::ALIVE,
+            (Cell::DEAD, Some(_), _) => Cell::DEAD,
+            (Cell::ALIVE, _, Some(p)) if self.rng.gen_bool(p) => Cell::ALIVE,
+            (Cell::ALIVE, _, Some(_)) if states > 2 => Cell(2),
+            (Cell::ALIVE, _, Some(_)) => Cell::DEAD,
+            _ => next,
+        }
+    }
+
+    /// Flips random cells of `board`: dead ones come alive and the rest die
+    pub fn add_noise(&mut self, board: &mut Board) {
+        if self.noise == 0.0 {
+            return;
+        }
+
+        for cell in board.data_mut() {
+            if self.rng.gen_bool(self.noise) {
+                *cell = if *cell == Cell::DEAD { Cell::ALIVE } else { Cell::DEAD };
+            }
+        }
+    }
+}
+
+impl FromStr for Chance {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chance = Chance::new(None);
+        chance.apply(s)?;
+        Ok(chance)
+    }
+}
+
+impl Display for Chance {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let mut settings = Vec::new();
+        for (name, table) in [("b", &self.birth), ("s", &self.survival)] {
+            // counts with the same chance are written together, as in `s23=0.5`
+            let mut written = [false; 9];
+            for count in 0..9 {
+                if let (Some(p), false) = (table[count], written[count]) {
+                    let counts: String = (count..9)
+                        .filter(|&c| table[c] == Some(p))
+                        .inspect(|&c| written[c] = true)
+                        .map(|c| char::from(b'0' + c as u8))
+                        .collect();
+                    settings.push(format!("{}{}={}", name, counts, p));
+                }
+            }
+        }
+        if self.noise > 0.0 {
+            settings.push(format!("noise={}", self.noise));
+        }
+        settings.push(format!("seed={}", self.seed));
+
+        write!(f, "{}", settings.join(","))
+    }
+}
+
+#[test]
+fn test_chance() {
+    let mut chance: Chance = "b3=0.5,s23=0.9,noise=0.01,seed=7".parse().unwrap();
+    assert_eq!(chance.to_string(), "b3=0.5,s23=0.9,noise=0.01,seed=7");
+    chance.apply("s3=-,b6=1,noise=0").unwrap();
+    assert_eq!(chance.to_string(), "b3=0.5,b6=1,s2=0.9,seed=7");
+    assert!("b9=0.5".parse::<Chance>().is_err());
+    assert!("b3=2".parse::<Chance>().is_err());
+    assert!("x3=0.5".parse::<Chance>().is_err());
+
+    // counts without a chance follow the rule, and certain chances always happen
+    assert_eq!(chance.decide(Cell::DEAD, 2, Cell::ALIVE, 2), Cell::ALIVE);
+    assert_eq!(chance.decide(Cell::DEAD, 6, Cell::DEAD, 2), Cell::ALIVE);
+    chance.apply("s4=0").unwrap();
+    assert_eq!(chance.decide(Cell::ALIVE, 4, Cell::ALIVE, 3), Cell(2));
+
+    // the same seed gives the same births and the same noise
+    let run = |chance: &mut Chance| {
+        let mut board = Board::new([16, 16], Cell::DEAD);
+        chance.noise = 0.2;
+        chance.add_noise(&mut board);
+        let births: Vec<Cell> = (0..32).map(|_| chance.decide(Cell::DEAD, 3, Cell::DEAD, 2)).collect();
+        (board.data().to_vec(), births)
+    };
+    chance.reseed();
+    let first = run(&mut chance);
+    chance.reseed();
+    assert_eq!(run(&mut chance), first);
+    assert!(first.0.contains(&Cell::ALIVE) && first.1.contains(&Cell::DEAD));
+
+    // rules that don't count neighbors like life-like ones only take noise
+    assert!(chance.check(&"B36/S23".parse().unwrap()).is_ok());
+    assert!(chance.check(&"B2/S/C3".parse().unwrap()).is_ok());
+    assert!(chance.check(&"R5,C0,M1,S34..58,B34..45,NM".parse().unwrap()).is_err());
+    assert!(chance.check(&"immigration".parse().unwrap()).is_err());
+    assert!(chance.check(&"wireworld".parse().unwrap()).is_err());
+    assert!("noise=0.1".parse::<Chance>().unwrap().check(&"wireworld".parse().unwrap()).is_ok());
+}
@@ -0,0 +1,173 @@
+use crossterm::style::Color;
+use std::collections::VecDeque;
+use std::fmt::Display;
+use std::str::FromStr;
+
+use super::rule::{neighbor_mask, LifeLike};
+use super::Cell;
+
+/// A life-like rule played by several teams, such as Immigration (2 teams) and
+/// QuadLife (4 teams). Cells are born with the color most of their parents have,
+/// and keep their color while they survive
+#[derive(Clone, Debug, PartialEq)]
+pub struct Colored {
+    /// The rule for whether a cell is alive, whatever its team
+    pub life: LifeLike,
+    /// Number of teams, which are the states 1 to `teams`
+    pub teams: u8,
+    /// Colors of dead and then of each team
+    pub palette: Vec<Option<Color>>,
+}
+
+/// Team colors, in order
+const TEAM_COLORS: [(u8, u8, u8); 4] = [(255, 80, 80), (80, 160, 255), (255, 220, 60), (60, 220, 120)];
+
+impl Colored {
+    pub fn new(life: LifeLike, teams: u8) -> Self {
+        let mut palette = vec![None];
+        palette.extend((0..teams as usize).map(|t| {
+            let (r, g, b) = TEAM_COLORS[t % TEAM_COLORS.len()];
+            Some(Color::Rgb { r, g, b })
+        }));
+
+        Colored { life, teams, palette }
+    }
+
+    /// The state `cell` moves to, with `neighbors` in N, NE, E, SE, S, SW, W, NW order
+    pub fn next(&self, cell: Cell, neighbors: &[Cell; 8]) -> Cell {
+        // whether a cell lives only depends on which neighbors are alive, whatever their team
+        let alive = neighbors.map(|n| if n == Cell::DEAD { Cell::DEAD } else { Cell::ALIVE });
+        let mask = neighbor_mask(&alive) as usize;
+
+        match cell {
+            Cell::DEAD if self.life.birth[mask] => self.majority(neighbors),
+            Cell::DEAD => Cell::DEAD,
+            cell if self.life.survival[mask] => cell,
+            _ => Cell::DEAD,
+        }
+    }
+
+    /// The team most of the alive `neighbors` belong to. When each belongs to a
+    /// different team and only one team is missing, as with three parents in
+    /// QuadLife, the cell joins the missing team. Other ties go to the lowest team
+    fn majority(&self, neighbors: &[Cell; 8]) -> Cell {
+        let mut counts = vec![0; self.teams as usize + 1];
+        for n in neighbors.iter().filter(|&&n| n != Cell::DEAD) {
+            counts[n.0.min(self.teams) as usize] += 1;
+        }
+
+        let parents: usize = counts.iter().sum();
+        if parents + 1 == self.teams as usize && counts[1..].iter().all(|&c| c <= 1) {
+            let missing = counts[1..].iter().position(|&c| c == 0).unwrap();
+            return Cell(missing as u8 + 1);
+        }
+
+        let most = *counts[1..].iter().max().unwrap();
+        let team = counts[1..].iter().position(|&c| c == most).unwrap();
+        Cell(team as u8 + 1)
+    }
+}
+
+impl FromStr for Colored {
+    type Err = String;
+
+    /// `immigration` or `quadlife`, optionally followed by the life-like rule the
+    /// teams play, as in `immigration:B36/S23`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, rule) = s.split_once(':').unwrap_or((s, "B3/S23"));
+        let teams = match name.to_ascii_lowercase().as_str() {
+            "immigration" => 2,
+            "quadlife" => 4,
+            _ => return Err(format!("Invalid rule `{}`: expected immigration or quadlife", s)),
+        };
+
+        let life: LifeLike = rule.parse()?;
+        if life.states != 2 {
+            return Err(format!("Invalid rule `{}`: teams can't play a Generations rule", s));
+        }
+
+        Ok(Colored::new(life, teams))
+    }
+}
+
+impl Display for Colored {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let name = if self.teams == 2 { "immigration" } else { "quadlife" };
+        if self.life == LifeLike::default() {
+            write!(f, "{}", name)
+        } else {
+            write!(f, "{}:{}", name, self.life)
+        }
+    }
+}
+
+/// Population of each team over the latest generations
+#[derive(Clone, Debug, Default)]
+pub struct Census {
+    /// For each generation, the population of each team
+    history: VecDeque<Vec<usize>>,
+    capacity: usize,
+}
+
+impl Census {
+    /// Keeps the populations of the latest `capacity` generations
+    pub fn new(capacity: usize) -> Self {
+        Census { history: VecDeque::with_capacity(capacity), capacity }
+    }
+
+    pub fn record(&mut self, populations: Vec<usize>) {
+        if self.history.len() == self.capacity {
+            self.history.pop_front();
+        }
+        self.history.push_back(populations);
+    }
+
+    /// The latest population of each team
+    pub fn latest(&self) -> &[usize] {
+        self.history.back().map_or(&[], |p| &p[..])
+    }
+
+    /// The population of `team` over time as bars, scaled against the largest
+    /// population of any team
+    pub fn sparkline(&self, team: usize) -> String {
+        const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+        let most = self.history.iter().flatten().copied().max().unwrap_or(0).max(1);
+        self.history
+            .iter()
+            .map(|p| BARS[p.get(team).copied().unwrap_or(0) * (BARS.len() - 1) / most])
+            .collect()
+    }
+}
+
+#[test]
+fn test_colored() {
+    let quadlife: Colored = "quadlife".parse().unwrap();
+    assert_eq!((quadlife.teams, quadlife.to_string().as_str()), (4, "quadlife"));
+    let immigration: Colored = "Immigration:B36/S23".parse().unwrap();
+    assert_eq!((immigration.teams, immigration.to_string().as_str()), (2, "immigration:B36/S23"));
+    assert!("quadlife:B2/S/C3".parse::<Colored>().is_err());
+    assert!("trilife".parse::<Colored>().is_err());
+
+    // N, NE, E, SE, S, SW, W, NW
+    let n = |cells: [u8; 8]| cells.map(Cell);
+    assert_eq!(immigration.next(Cell::DEAD, &n([1, 2, 2, 0, 0, 0, 0, 0])), Cell(2));
+    assert_eq!(immigration.next(Cell(1), &n([2, 2, 0, 0, 0, 0, 0, 0])), Cell(1));
+    assert_eq!(immigration.next(Cell(2), &n([2, 0, 0, 0, 0, 0, 0, 0])), Cell::DEAD);
+
+    // three parents of different teams give birth to the fourth
+    assert_eq!(quadlife.next(Cell::DEAD, &n([1, 0, 2, 0, 4, 0, 0, 0])), Cell(3));
+    assert_eq!(quadlife.next(Cell::DEAD, &n([3, 0, 3, 0, 4, 0, 0, 0])), Cell(3));
+    assert_eq!(quadlife.next(Cell::DEAD, &n([3, 0, 3, 0, 4, 0, 1, 0])), Cell::DEAD);
+}
+
+#[test]
+fn test_census() {
+    let mut census = Census::new(3);
+    for populations in [vec![0, 7], vec![7, 4], vec![3, 0], vec![1, 1]] {
+        census.record(populations);
+    }
+    assert_eq!(census.latest(), &[1, 1]);
+    assert_eq!(census.sparkline(0), "█▄▂");
+    assert_eq!(census.sparkline(1), "▅▁▂");
+}
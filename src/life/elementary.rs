@@ -0,0 +1,108 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+use super::Cell;
+
+/// A one dimensional rule, either an elementary rule numbered as by Wolfram
+/// such as `W30` and `W110`, or a totalistic code over a wider radius such as
+/// `T20R2`. Each generation is one row of the board
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Elementary {
+    pub code: u64,
+    /// Cells on each side of a cell that it looks at
+    pub radius: usize,
+    /// Whether `code` is indexed by the number of alive cells rather than by their arrangement
+    pub totalistic: bool,
+}
+
+impl Elementary {
+    /// The generation after `row`. Cells past the ends are dead unless `wrap` is set
+    pub fn next_row(&self, row: &[Cell], wrap: bool) -> Vec<Cell> {
+        let width = row.len() as isize;
+        let r = self.radius as isize;
+        let alive = |x: isize| -> u64 {
+            match x {
+                x if wrap => (row[x.rem_euclid(width) as usize] == Cell::ALIVE) as u64,
+                x if x < 0 || x >= width => 0,
+                x => (row[x as usize] == Cell::ALIVE) as u64,
+            }
+        };
+
+        (0..width)
+            .map(|x| {
+                let index = if self.totalistic {
+                    (x - r..=x + r).map(alive).sum()
+                } else {
+                    // the leftmost cell is the highest bit, as in Wolfram's numbering
+                    (x - r..=x + r).fold(0, |index, x| index << 1 | alive(x))
+                };
+
+                if self.code >> index & 1 == 1 {
+                    Cell::ALIVE
+                } else {
+                    Cell::DEAD
+                }
+            })
+            .collect()
+    }
+}
+
+impl FromStr for Elementary {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || format!("Invalid rule `{}`: expected W0 to W255, or T<code>R<radius> such as T20R2", s);
+
+        if let Some(number) = s.strip_prefix(['W', 'w']) {
+            let code: u8 = number.parse().map_err(|_| err())?;
+            return Ok(Elementary { code: code as u64, radius: 1, totalistic: false });
+        }
+
+        let (code, radius) = s
+            .strip_prefix(['T', 't'])
+            .and_then(|rest| rest.split_once(['R', 'r']))
+            .ok_or_else(err)?;
+        let radius: usize = radius.parse().ok().filter(|r| (1..=30).contains(r)).ok_or_else(err)?;
+        let code: u64 = code.parse().map_err(|_| err())?;
+
+        // a sum of 2r + 1 cells has 2r + 2 possible values
+        if radius < 31 && code >> (2 * radius + 2) != 0 {
+            return Err(format!("Invalid rule `{}`: the code must be below {}", s, 1u64 << (2 * radius + 2)));
+        }
+
+        Ok(Elementary { code, radius, totalistic: true })
+    }
+}
+
+impl Display for Elementary {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if self.totalistic {
+            write!(f, "T{}R{}", self.code, self.radius)
+        } else {
+            write!(f, "W{}", self.code)
+        }
+    }
+}
+
+#[test]
+fn test_elementary() {
+    let row = |s: &str| -> Vec<Cell> { s.chars().map(|c| if c == '#' { Cell::ALIVE } else { Cell::DEAD }).collect() };
+
+    let rule_30: Elementary = "W30".parse().unwrap();
+    assert_eq!(rule_30.to_string(), "W30");
+    assert_eq!(rule_30.next_row(&row("...#..."), false), row("..###.."));
+    assert_eq!(rule_30.next_row(&row("..###.."), false), row(".##..#."));
+
+    let rule_110: Elementary = "w110".parse().unwrap();
+    assert_eq!(rule_110.next_row(&row("#...."), true), row("#...#"));
+    assert_eq!(rule_110.next_row(&row("#...."), false), row("#...."));
+
+    // code 20 with radius 2 turns on with 2 or 4 alive cells among 5
+    let code_20: Elementary = "T20R2".parse().unwrap();
+    assert_eq!(code_20.to_string(), "T20R2");
+    assert_eq!(code_20.next_row(&row("..#..#.."), false), row("...##..."));
+
+    assert!("W256".parse::<Elementary>().is_err());
+    assert!("T64R2".parse::<Elementary>().is_err());
+    assert!("T20".parse::<Elementary>().is_err());
+}
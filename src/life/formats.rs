@@ -0,0 +1,327 @@
+use super::rule::Rule;
+use super::{Board, Cell};
+use std::path::Path;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// The bit packed format written by `saver::save`
+    Life,
+    /// Plaintext `.cells` files (`.` for dead, `O` for alive)
+    Cells,
+    /// Run length encoded `.rle` files
+    Rle,
+}
+
+impl Format {
+    pub fn from_path(path: &Path) -> Option<Format> {
+        match path.extension()?.to_str()? {
+            "life" | "dat" => Some(Format::Life),
+            "cells" | "txt" => Some(Format::Cells),
+            "rle" => Some(Format::Rle),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "life" => Ok(Format::Life),
+            "cells" | "plaintext" | "text" => Ok(Format::Cells),
+            "rle" => Ok(Format::Rle),
+            _ => Err(format!("Unknown format `{}`", s)),
+        }
+    }
+}
+
+/// Loads a board from `path`, picking the format from the file extension
+pub fn load(path: &Path) -> Result<Board, String> {
+    if path.extension().is_some_and(|ext| ext == "lenia") {
+        return match super::lenia::load(path)? {
+            (_, Some(field)) => Ok(super::lenia::quantize(&field)),
+            (_, None) => Err(format!("{} only holds Lenia parameters", path.display())),
+        };
+    }
+
+    let format = Format::from_path(path)
+        .ok_or_else(|| format!("Unrecognised pattern extension: {}", path.display()))?;
+
+    match format {
+        Format::Life => super::loader::load(path.to_str().unwrap())
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e)),
+        Format::Cells | Format::Rle => {
+            let text = std::fs::read_to_string(path)
+                .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+
+            if format == Format::Cells {
+                read_cells(&text)
+            } else {
+                read_rle(&text)
+            }
+        }
+    }
+}
+
+/// Writes `board` to `path` in `format`. `comments` are embedded in the text formats
+pub fn save(path: &Path, board: &Board, rule: &Rule, format: Format, comments: &[String]) -> Result<(), String> {
+    let result = match format {
+        Format::Life => super::saver::save(path.to_str().unwrap(), board),
+        Format::Cells => std::fs::write(path, write_cells(board, comments)?),
+        Format::Rle => std::fs::write(path, write_rle(board, rule, comments)),
+    };
+
+    result.map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+pub fn read_cells(text: &str) -> Result<Board, String> {
+    let rows: Vec<&str> = text
+        .lines()
+        .filter(|line| !line.starts_with('!'))
+        .collect();
+
+    let width = rows.iter().map(|row| row.trim_end().chars().count()).max().unwrap_or(0);
+    if width == 0 || rows.is_empty() {
+        return Err(String::from("Pattern is empty"));
+    }
+
+    let mut board = Board::new([width, rows.len()], Cell::DEAD);
+    for (y, row) in rows.iter().enumerate() {
+        for (x, c) in row.trim_end().chars().enumerate() {
+            board[[x, y]] = match c {
+                '.' => Cell::DEAD,
+                'O' | '*' => Cell::ALIVE,
+                _ => return Err(format!("Unexpected character `{}` at {}:{}", c, y + 1, x + 1)),
+            };
+        }
+    }
+
+    Ok(board)
+}
+
+pub fn write_cells(board: &Board, comments: &[String]) -> Result<String, String> {
+    if board.data().iter().any(|cell| cell.0 > 1) {
+        return Err(String::from("The cells format can only hold dead and alive cells, use rle instead"));
+    }
+
+    let mut output = String::new();
+
+    for comment in comments {
+        output.push_str(&format!("!{}\n", comment));
+    }
+
+    for y in 0..board.height() {
+        for x in 0..board.width() {
+            output.push(if board[[x, y]] == Cell::ALIVE { 'O' } else { '.' });
+        }
+        output.push('\n');
+    }
+
+    Ok(output)
+}
+
+pub fn read_rle(text: &str) -> Result<Board, String> {
+    let mut lines = text.lines().filter(|line| !line.starts_with('#'));
+    let header = lines.next().ok_or_else(|| String::from("Missing RLE header"))?;
+
+    let mut dims = [None, None];
+    for field in header.split(',') {
+        let (key, value) = match field.split_once('=') {
+            Some((key, value)) => (key.trim(), value.trim()),
+            None => return Err(format!("Malformed RLE header `{}`", header)),
+        };
+
+        match key {
+            "x" => dims[0] = value.parse().ok(),
+            "y" => dims[1] = value.parse().ok(),
+            _ => {}
+        }
+    }
+
+    let (width, height) = match dims {
+        [Some(w), Some(h)] if w > 0 && h > 0 => (w, h),
+        _ => return Err(format!("Invalid RLE dimensions in `{}`", header)),
+    };
+
+    let mut board = Board::new([width, height], Cell::DEAD);
+    let (mut x, mut y) = (0, 0);
+    let mut count = String::new();
+    let mut prefix = None;
+
+    'outer: for line in lines {
+        for c in line.trim().chars() {
+            if c.is_ascii_digit() {
+                count.push(c);
+                continue;
+            }
+
+            // multi-state patterns write states above 24 as a prefix from `p` to `y` and a letter
+            if ('p'..='y').contains(&c) {
+                prefix = Some(c);
+                continue;
+            }
+
+            let run = match count.as_str() {
+                "" => 1,
+                count => count.parse().map_err(|_| format!("Bad run count `{}`", count))?,
+            };
+            count.clear();
+
+            let state = match (prefix.take(), c) {
+                (None, 'b' | '.') => 0,
+                (None, 'o') => 1,
+                (None, 'A'..='X') => c as u8 - b'A' + 1,
+                (Some(p), 'A'..='X') => {
+                    let state = (p as usize - 'p' as usize + 1) * 24 + (c as usize - 'A' as usize + 1);
+                    u8::try_from(state).map_err(|_| format!("RLE state `{}{}` is above 255", p, c))?
+                }
+                (None, '$') => {
+                    y += run;
+                    x = 0;
+                    continue;
+                }
+                (None, '!') => break 'outer,
+                _ => return Err(format!("Unexpected RLE character `{}`", c)),
+            };
+
+            for _ in 0..run {
+                if state != 0 {
+                    if x >= width || y >= height {
+                        return Err(String::from("RLE pattern exceeds its declared size"));
+                    }
+                    board[[x, y]] = Cell(state);
+                }
+                x += 1;
+            }
+        }
+    }
+
+    Ok(board)
+}
+
+pub fn write_rle(board: &Board, rule: &Rule, comments: &[String]) -> String {
+    let mut output = String::new();
+
+    for comment in comments {
+        output.push_str(&format!("#C {}\n", comment));
+    }
+    output.push_str(&format!("x = {}, y = {}, rule = {}\n", board.width(), board.height(), rule));
+
+    // each run is kept whole, its count with its tag, so lines only break between runs
+    let mut items = Vec::new();
+    let mut push_run = |run: usize, tag: &str| match run {
+        0 => {}
+        1 => items.push(tag.to_string()),
+        run => items.push(format!("{}{}", run, tag)),
+    };
+
+    let multi_state = board.data().iter().any(|cell| cell.0 > 1);
+    let tag = |cell: Cell| match (multi_state, cell.0) {
+        (false, 0) => String::from("b"),
+        (false, _) => String::from("o"),
+        (true, 0) => String::from("."),
+        (true, s) if s <= 24 => char::from(b'A' + s - 1).to_string(),
+        (true, s) => format!("{}{}", char::from(b'p' + (s - 1) / 24 - 1), char::from(b'A' + (s - 1) % 24)),
+    };
+
+    let mut last_row = 0;
+    for y in 0..board.height() {
+        let row: Vec<Cell> = (0..board.width()).map(|x| board[[x, y]]).collect();
+        let end = match row.iter().rposition(|&cell| cell != Cell::DEAD) {
+            Some(end) => end + 1,
+            None => continue,
+        };
+
+        push_run(y - last_row, "$");
+        last_row = y;
+
+        let mut x = 0;
+        while x < end {
+            let cell = row[x];
+            let run = row[x..end].iter().take_while(|&&c| c == cell).count();
+            push_run(run, &tag(cell));
+            x += run;
+        }
+    }
+    push_run(1, "!");
+
+    // keep lines to 70 characters as the format recommends
+    let mut line_len = 0;
+    for item in items {
+        if line_len > 0 && line_len + item.len() > 70 {
+            output.push('\n');
+            line_len = 0;
+        }
+        output.push_str(&item);
+        line_len += item.len();
+    }
+    output.push('\n');
+
+    output
+}
+
+#[test]
+fn test_rle_round_trip() {
+    let mut board = Board::new([6, 4], Cell::DEAD);
+    board[[1, 0]] = Cell::ALIVE;
+    board[[2, 1]] = Cell::ALIVE;
+    board[[0, 2]] = Cell::ALIVE;
+    board[[1, 2]] = Cell::ALIVE;
+    board[[2, 2]] = Cell::ALIVE;
+
+    let rle = write_rle(&board, &Rule::default(), &[]);
+    assert!(rle.contains("bo$2bo$3o!"));
+    assert!(read_rle(&rle).unwrap().data() == board.data());
+    assert!(read_cells(&write_cells(&board, &[]).unwrap()).unwrap().data() == board.data());
+
+    board[[4, 3]] = Cell(2);
+    board[[5, 3]] = Cell(30);
+    let rle = write_rle(&board, &"B2/S/C31".parse().unwrap(), &[]);
+    assert!(rle.contains(".A$2.A$3A$4.BpF!"));
+    assert!(read_rle(&rle).unwrap().data() == board.data());
+    assert!(write_cells(&board, &[]).is_err());
+}
+
+#[test]
+fn test_rle_wrapping() {
+    // alternating runs of differing lengths make a body far longer than a line
+    let mut board = Board::new([400, 2], Cell::DEAD);
+    let mut x = 0;
+    for run in (1..).map(|i| i % 13 + 1) {
+        if x + run > board.width() {
+            break;
+        }
+        for x in x..x + run {
+            board[[x, 0]] = Cell::ALIVE;
+        }
+        x += run * 2;
+    }
+    board[[399, 1]] = Cell(30);
+
+    for rule in ["B3/S23", "B2/S/C31"] {
+        let rle = write_rle(&board, &rule.parse().unwrap(), &[]);
+        let body: Vec<&str> = rle.lines().skip(1).collect();
+        assert!(body.len() > 1);
+        assert!(body.iter().all(|line| line.len() <= 70));
+        assert!(body.iter().all(|line| !line.ends_with(|c: char| c.is_ascii_digit())));
+        assert!(body.iter().all(|line| !line.ends_with(|c: char| ('p'..='y').contains(&c))));
+        assert!(read_rle(&rle).unwrap().data() == board.data());
+    }
+
+    let huge = "x = 3, y = 1\n99999999999999999999999o!";
+    assert_eq!(read_rle(huge).unwrap_err(), "Bad run count `99999999999999999999999`");
+}
+
+#[test]
+fn test_rle_high_states() {
+    let mut board = Board::new([3, 1], Cell::DEAD);
+    board[[0, 0]] = Cell(192);
+    board[[2, 0]] = Cell(255);
+    let rle = write_rle(&board, &"B2/S/C255".parse().unwrap(), &[]);
+    assert!(rle.contains("vX.yO!"));
+    assert!(read_rle(&rle).unwrap().data() == board.data());
+
+    assert_eq!(read_rle("x = 1, y = 1\nyP!").unwrap_err(), "RLE state `yP` is above 255");
+}
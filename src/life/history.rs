@@ -0,0 +1,199 @@
+use super::turmite::Ant;
+use super::{lenia, loader, saver, Life};
+use rand::rngs::StdRng;
+use std::collections::VecDeque;
+
+/// A generation kept for rewinding, with the board in the bit-packed encoding
+/// boards are saved in
+pub struct Snapshot {
+    board: Vec<u8>,
+    /// The exact values of a Lenia board, which its states are rounded from
+    field: Option<lenia::Field>,
+    /// Where the chances' random numbers were up to, so a run with chances
+    /// repeats the same way going forward from here again
+    chance: Option<StdRng>,
+    ants: Vec<Ant>,
+    row: usize,
+    phase: u8,
+    dead: bool,
+}
+
+impl Life {
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            board: saver::encode(&self.board),
+            field: self.field.clone(),
+            chance: self.chance.as_ref().map(|chance| chance.position()),
+            ants: self.ants.clone(),
+            row: self.row,
+            phase: self.phase,
+            dead: self.dead,
+        }
+    }
+
+    /// Goes back to a snapshot. Ages start over, and a snapshot from before
+    /// the board was resized is fitted to its size now
+    pub fn restore(&mut self, snapshot: &Snapshot) {
+        let board = loader::decode(&snapshot.board).expect("snapshots are whole encoded boards");
+        let resized = (board.width(), board.height()) != self.dims();
+        self.board = match resized {
+            false => board,
+            true => super::resized(&board, self.dims(), super::Cell::DEAD),
+        };
+        self.field = match (&snapshot.field, resized) {
+            (Some(field), true) => Some(super::resized(field, self.dims(), 0.0)),
+            (field, _) => field.clone(),
+        };
+        if let (Some(chance), Some(position)) = (&mut self.chance, &snapshot.chance) {
+            chance.rewind(position.clone());
+        }
+        let (w, h) = self.dims();
+        self.ants = snapshot.ants.iter().filter(|ant| ant.x < w && ant.y < h).cloned().collect();
+        self.row = snapshot.row.min(h - 1);
+        self.phase = snapshot.phase;
+        self.dead = snapshot.dead;
+        self.ages.data_mut().fill(0);
+    }
+}
+
+/// The latest generations of a run, oldest first, which can be stepped back
+/// and forward through
+pub struct History {
+    snapshots: VecDeque<(usize, Snapshot)>,
+    capacity: usize,
+    /// Index of the generation shown, once stepped back from the latest
+    position: Option<usize>,
+}
+
+impl History {
+    /// Keeps up to `capacity` generations, none if it is 0
+    pub fn new(capacity: usize) -> Self {
+        History { snapshots: VecDeque::with_capacity(capacity), capacity, position: None }
+    }
+
+    /// Adds the generation `life` is at as the latest, dropping the oldest once
+    /// full. Stepping back and carrying on from there forgets the generations
+    /// that came after
+    pub fn push(&mut self, generation: usize, life: &Life) {
+        if let Some(position) = self.position.take() {
+            self.snapshots.truncate(position + 1);
+        }
+        if self.capacity == 0 {
+            return;
+        }
+        if self.snapshots.len() == self.capacity {
+            self.snapshots.pop_front();
+        }
+        self.snapshots.push_back((generation, life.snapshot()));
+    }
+
+    /// Sets `life` one generation back and returns which generation that is,
+    /// or `None` at the oldest one kept
+    pub fn back(&mut self, life: &mut Life) -> Option<usize> {
+        let position = self.position.unwrap_or(self.snapshots.len().checked_sub(1)?).checked_sub(1)?;
+        self.position = Some(position);
+        let (generation, snapshot) = &self.snapshots[position];
+        life.restore(snapshot);
+        Some(*generation)
+    }
+
+    /// Sets `life` one generation forward after stepping back, or `None` if
+    /// it is at the latest
+    pub fn forward(&mut self, life: &mut Life) -> Option<usize> {
+        let position = self.position? + 1;
+        self.position = Some(position).filter(|&position| position + 1 < self.snapshots.len());
+        let (generation, snapshot) = &self.snapshots[position];
+        life.restore(snapshot);
+        Some(*generation)
+    }
+
+    /// How many generations back from the latest the one shown is
+    pub fn behind(&self) -> usize {
+        self.position.map_or(0, |position| self.snapshots.len() - 1 - position)
+    }
+}
+
+#[test]
+fn test_history() {
+    use super::{Board, Cell};
+
+    let mut board = Board::new([5, 5], Cell::DEAD);
+    for y in 1..4 {
+        board[[2, y]] = Cell::ALIVE;
+    }
+    let mut life = Life::new((5, 5), '.', 'O', None, Some(board.clone()));
+    let mut history = History::new(3);
+    history.push(0, &life);
+    for generation in 1..=4 {
+        life.tick();
+        history.push(generation, &life);
+    }
+
+    // only the latest three are kept
+    assert_eq!(history.back(&mut life), Some(3));
+    assert_eq!(history.back(&mut life), Some(2));
+    assert!(life.board.data() == board.data());
+    assert_eq!(history.back(&mut life), None);
+    assert_eq!(history.behind(), 2);
+    assert_eq!(history.forward(&mut life), Some(3));
+    assert_eq!(history.forward(&mut life), Some(4));
+    assert_eq!(history.forward(&mut life), None);
+
+    // carrying on from a past generation forgets the ones after it
+    history.back(&mut life);
+    history.back(&mut life);
+    life.board[[0, 0]] = Cell::ALIVE;
+    history.push(3, &life);
+    assert_eq!(history.behind(), 0);
+    assert_eq!(history.back(&mut life), Some(2));
+    assert_eq!(history.forward(&mut life), Some(3));
+    assert_eq!(life.board[[0, 0]], Cell::ALIVE);
+}
+
+#[test]
+fn test_history_exact() {
+    use super::lenia::Field;
+    use super::{Board, Cell, Rule};
+    use std::rc::Rc;
+
+    // going back and forward again runs the same as the first time through
+    let replay = |life: &mut Life| {
+        let mut history = History::new(8);
+        history.push(0, life);
+        let mut ticks = Vec::new();
+        for generation in 1..=4 {
+            life.tick();
+            history.push(generation, life);
+            ticks.push((life.board.clone(), life.field.clone()));
+        }
+        for _ in 0..3 {
+            history.back(life);
+        }
+        history.push(1, life);
+        for (board, field) in &ticks[1..] {
+            life.tick();
+            assert!(life.board.data() == board.data());
+            assert!(life.field.as_ref().map(|field| field.data()) == field.as_ref().map(|field| field.data()));
+        }
+    };
+
+    let mut field = Field::new([16, 16], 0.0);
+    for (i, ([x, y], value)) in (&mut field).into_iter().enumerate() {
+        if (4..12).contains(&x) && (4..12).contains(&y) {
+            *value = (i % 7) as f32 / 7.0 + 0.013;
+        }
+    }
+    let mut lenia = Life::new((16, 16), ' ', '#', None, None);
+    lenia.rule = Rule::Lenia(Rc::new("lenia:R=3".parse().unwrap()));
+    lenia.set_field(field);
+    replay(&mut lenia);
+
+    let mut board = Board::new([16, 16], Cell::DEAD);
+    for y in 4..12 {
+        board[[y, y]] = Cell::ALIVE;
+        board[[y, 15 - y]] = Cell::ALIVE;
+    }
+    let mut chances = Life::new((16, 16), '.', 'O', None, Some(board));
+    chances.chance = Some("b2=0.5,s1=0.5,noise=0.05,seed=3".parse().unwrap());
+    replay(&mut chances);
+}
@@ -0,0 +1,430 @@
+use crossterm::style::Color;
+use std::path::Path;
+
+use super::rule::Rule;
+use super::theme::Theme;
+use super::Board;
+
+/// How a board is drawn as an image
+#[derive(Clone, Debug)]
+pub struct Render {
+    /// Width and height of each cell in pixels
+    pub cell_size: usize,
+    /// Color of the lines between cells, or `None` for no grid
+    pub grid: Option<[u8; 3]>,
+    /// Color of each state, starting with dead
+    pub palette: Vec<[u8; 3]>,
+}
+
+impl Render {
+    /// Colors every state of `rule` as the terminal would with `theme`, on a
+    /// white background with black alive cells for whatever the theme leaves unset
+    pub fn new(rule: &Rule, theme: &Theme, cell_size: usize, grid: Option<[u8; 3]>) -> Self {
+        let dead = theme.dead.or(theme.background).and_then(rgb).unwrap_or([255, 255, 255]);
+        let alive = theme.alive.and_then(rgb).unwrap_or([0, 0, 0]);
+        let states = rule.states();
+
+        let palette = (0..states)
+            .map(|state| {
+                if let Some(color) = rule.colors().get(state as usize).copied().flatten().and_then(rgb) {
+                    return color;
+                }
+
+                match state {
+                    0 => dead,
+                    1 if !matches!(rule, Rule::Lenia(_)) => alive,
+                    // Lenia levels grow from dead to alive, and dying states fade back
+                    _ if matches!(rule, Rule::Lenia(_)) => mix(dead, alive, state as f32 / (states - 1) as f32),
+                    _ => mix(alive, dead, (state - 1) as f32 / (states - 1) as f32),
+                }
+            })
+            .collect();
+
+        Render { cell_size: cell_size.max(1), grid, palette }
+    }
+
+    /// Size of the image of a board of `dims` in pixels
+    pub fn size(&self, dims: [usize; 2]) -> (usize, usize) {
+        let line = self.grid.is_some() as usize;
+        (dims[0] * (self.cell_size + line) + line, dims[1] * (self.cell_size + line) + line)
+    }
+
+    /// The index into `colors` of every pixel of the image of `board`, row by row
+    fn pixels(&self, board: &Board) -> Vec<u8> {
+        let (width, height) = self.size([board.width(), board.height()]);
+        let line = self.grid.is_some() as usize;
+        let grid = self.palette.len() as u8;
+        let step = self.cell_size + line;
+
+        let mut pixels = Vec::with_capacity(width * height);
+        for py in 0..height {
+            for px in 0..width {
+                let on_line = line == 1 && (px % step == 0 || py % step == 0);
+                pixels.push(match on_line {
+                    true => grid,
+                    false => board[[(px - line) / step, (py - line) / step]].0.min(grid - 1),
+                });
+            }
+        }
+
+        pixels
+    }
+
+    /// The palette followed by the grid color
+    fn colors(&self) -> Vec<[u8; 3]> {
+        let mut colors = self.palette.clone();
+        colors.push(self.grid.unwrap_or([0, 0, 0]));
+        colors
+    }
+}
+
+/// The red, green and blue of a terminal color, as xterm draws the named ones
+pub fn rgb(color: Color) -> Option<[u8; 3]> {
+    const ANSI: [[u8; 3]; 16] = [
+        [0, 0, 0], [205, 0, 0], [0, 205, 0], [205, 205, 0], [0, 0, 238], [205, 0, 205], [0, 205, 205], [229, 229, 229],
+        [127, 127, 127], [255, 0, 0], [0, 255, 0], [255, 255, 0], [92, 92, 255], [255, 0, 255], [0, 255, 255], [255, 255, 255],
+    ];
+
+    Some(match color {
+        Color::Rgb { r, g, b } => [r, g, b],
+        Color::Black => ANSI[0],
+        Color::DarkRed => ANSI[1],
+        Color::DarkGreen => ANSI[2],
+        Color::DarkYellow => ANSI[3],
+        Color::DarkBlue => ANSI[4],
+        Color::DarkMagenta => ANSI[5],
+        Color::DarkCyan => ANSI[6],
+        Color::Grey => ANSI[7],
+        Color::DarkGrey => ANSI[8],
+        Color::Red => ANSI[9],
+        Color::Green => ANSI[10],
+        Color::Yellow => ANSI[11],
+        Color::Blue => ANSI[12],
+        Color::Magenta => ANSI[13],
+        Color::Cyan => ANSI[14],
+        Color::White => ANSI[15],
+        Color::AnsiValue(n) if n < 16 => ANSI[n as usize],
+        // the 6x6x6 color cube and then a ramp of greys
+        Color::AnsiValue(n) if n < 232 => {
+            let level = |c: u8| if c == 0 { 0 } else { 55 + c * 40 };
+            let n = n - 16;
+            [level(n / 36), level(n / 6 % 6), level(n % 6)]
+        }
+        Color::AnsiValue(n) => [8 + (n - 232) * 10; 3],
+        Color::Reset => return None,
+    })
+}
+
+fn mix(from: [u8; 3], to: [u8; 3], t: f32) -> [u8; 3] {
+    std::array::from_fn(|i| (from[i] as f32 + (to[i] as f32 - from[i] as f32) * t).round() as u8)
+}
+
+/// Encodes `board` as a PNG
+pub fn png(board: &Board, render: &Render) -> Vec<u8> {
+    let (width, height) = render.size([board.width(), board.height()]);
+    let pixels = render.pixels(board);
+
+    // each row starts with its filter type, 0 for none
+    let mut raw = Vec::with_capacity((width + 1) * height);
+    for row in pixels.chunks(width) {
+        raw.push(0);
+        raw.extend_from_slice(row);
+    }
+
+    let mut header = Vec::new();
+    header.extend_from_slice(&(width as u32).to_be_bytes());
+    header.extend_from_slice(&(height as u32).to_be_bytes());
+    // 8 bits per pixel, indexed color, then the only compression, filtering and no interlacing
+    header.extend_from_slice(&[8, 3, 0, 0, 0]);
+
+    let mut out = b"\x89PNG\r\n\x1a\n".to_vec();
+    png_chunk(&mut out, b"IHDR", &header);
+    png_chunk(&mut out, b"PLTE", &render.colors().concat());
+    png_chunk(&mut out, b"IDAT", &zlib(&raw));
+    png_chunk(&mut out, b"IEND", &[]);
+    out
+}
+
+fn png_chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = out.len();
+    out.extend_from_slice(kind);
+    out.extend_from_slice(data);
+    let crc = crc32(&out[start..]);
+    out.extend_from_slice(&crc.to_be_bytes());
+}
+
+pub fn save_png(path: &Path, board: &Board, render: &Render) -> Result<(), String> {
+    std::fs::write(path, png(board, render)).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { 0xedb8_8320 ^ (crc >> 1) } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    b << 16 | a
+}
+
+/// Writes bits starting from the lowest bit of each byte, as deflate and GIF do
+#[derive(Default)]
+struct Bits {
+    out: Vec<u8>,
+    buffer: u64,
+    count: u32,
+}
+
+impl Bits {
+    fn write(&mut self, value: u32, count: u32) {
+        self.buffer |= (value as u64) << self.count;
+        self.count += count;
+        while self.count >= 8 {
+            self.out.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    /// Writes a Huffman code, which deflate stores from its highest bit
+    fn code(&mut self, code: u32, count: u32) {
+        self.write(code.reverse_bits() >> (32 - count), count);
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.out.push(self.buffer as u8);
+        }
+        self.out
+    }
+}
+
+const LENGTH_BASES: [u16; 29] =
+    [3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258];
+const LENGTH_EXTRA: [u8; 29] = [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0];
+const DISTANCE_BASES: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145,
+    8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] =
+    [0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13];
+
+/// Compresses `data` into a zlib stream: a single deflate block with the fixed
+/// Huffman codes, finding repeats through a hash of the next three bytes
+fn zlib(data: &[u8]) -> Vec<u8> {
+    const WINDOW: usize = 32768;
+    const MAX_CHAIN: usize = 32;
+
+    let literal = |bits: &mut Bits, symbol: u32| match symbol {
+        0..=143 => bits.code(0x30 + symbol, 8),
+        144..=255 => bits.code(0x190 + symbol - 144, 9),
+        256..=279 => bits.code(symbol - 256, 7),
+        _ => bits.code(0xc0 + symbol - 280, 8),
+    };
+
+    let mut bits = Bits::default();
+    // the last block, compressed with the fixed codes
+    bits.write(1, 1);
+    bits.write(1, 2);
+
+    let hash = |i: usize| (data[i] as usize) << 10 ^ (data[i + 1] as usize) << 5 ^ data[i + 2] as usize;
+    let mut head = vec![usize::MAX; 1 << 15];
+    let mut prev = vec![usize::MAX; data.len()];
+    let insert = |i: usize, head: &mut [usize], prev: &mut [usize]| {
+        if i + 2 < data.len() {
+            let h = hash(i) & 0x7fff;
+            prev[i] = head[h];
+            head[h] = i;
+        }
+    };
+
+    let mut i = 0;
+    while i < data.len() {
+        let (mut best_len, mut best_dist) = (0, 0);
+        if i + 2 < data.len() {
+            let mut candidate = head[hash(i) & 0x7fff];
+            let mut chain = 0;
+            while candidate != usize::MAX && i - candidate <= WINDOW && chain < MAX_CHAIN {
+                let len = (0..258.min(data.len() - i)).take_while(|&k| data[candidate + k] == data[i + k]).count();
+                if len > best_len {
+                    (best_len, best_dist) = (len, i - candidate);
+                }
+                candidate = prev[candidate];
+                chain += 1;
+            }
+        }
+
+        if best_len >= 3 {
+            let code = LENGTH_BASES.iter().rposition(|&base| base as usize <= best_len).unwrap();
+            literal(&mut bits, 257 + code as u32);
+            bits.write((best_len - LENGTH_BASES[code] as usize) as u32, LENGTH_EXTRA[code] as u32);
+
+            let code = DISTANCE_BASES.iter().rposition(|&base| base as usize <= best_dist).unwrap();
+            bits.code(code as u32, 5);
+            bits.write((best_dist - DISTANCE_BASES[code] as usize) as u32, DISTANCE_EXTRA[code] as u32);
+
+            for k in i..i + best_len {
+                insert(k, &mut head, &mut prev);
+            }
+            i += best_len;
+        } else {
+            literal(&mut bits, data[i] as u32);
+            insert(i, &mut head, &mut prev);
+            i += 1;
+        }
+    }
+    literal(&mut bits, 256);
+
+    // default compression, with a check value that makes the header a multiple of 31
+    let mut out = vec![0x78, 0x9c];
+    out.extend(bits.finish());
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+/// Encodes `frames` as an animated GIF that loops forever, showing each frame
+/// for `delay` hundredths of a second
+pub fn gif(frames: &[Board], render: &Render, delay: u16) -> Result<Vec<u8>, String> {
+    let first = frames.first().ok_or_else(|| String::from("No frames to write"))?;
+    let (width, height) = render.size([first.width(), first.height()]);
+    if width > u16::MAX as usize || height > u16::MAX as usize {
+        return Err(format!("A {}x{} image is too large for a GIF", width, height));
+    }
+
+    let colors = render.colors();
+    if colors.len() > 256 {
+        return Err(String::from("A GIF can't have more than 255 states and a grid"));
+    }
+    // the color table has a power of two entries, at least 4 so codes start at 3 bits
+    let depth = (usize::BITS - (colors.len() - 1).leading_zeros()).max(2);
+
+    let mut out = b"GIF89a".to_vec();
+    out.extend_from_slice(&(width as u16).to_le_bytes());
+    out.extend_from_slice(&(height as u16).to_le_bytes());
+    out.extend_from_slice(&[0x80 | ((depth as u8 - 1) << 4) | (depth as u8 - 1), 0, 0]);
+    for i in 0..1 << depth {
+        out.extend_from_slice(&colors.get(i).copied().unwrap_or([0, 0, 0]));
+    }
+
+    // loop forever
+    out.extend_from_slice(b"\x21\xff\x0bNETSCAPE2.0\x03\x01\x00\x00\x00");
+
+    for frame in frames {
+        out.extend_from_slice(&[0x21, 0xf9, 4, 0x04]);
+        out.extend_from_slice(&delay.to_le_bytes());
+        out.extend_from_slice(&[0, 0]);
+
+        out.push(0x2c);
+        out.extend_from_slice(&[0, 0, 0, 0]);
+        out.extend_from_slice(&(width as u16).to_le_bytes());
+        out.extend_from_slice(&(height as u16).to_le_bytes());
+        out.push(0);
+
+        out.push(depth as u8);
+        for block in lzw(&render.pixels(frame), depth).chunks(255) {
+            out.push(block.len() as u8);
+            out.extend_from_slice(block);
+        }
+        out.push(0);
+    }
+
+    out.push(0x3b);
+    Ok(out)
+}
+
+pub fn save_gif(path: &Path, frames: &[Board], render: &Render, delay: u16) -> Result<(), String> {
+    std::fs::write(path, gif(frames, render, delay)?).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Compresses `pixels` with GIF's variant of LZW, starting with codes one bit
+/// wider than `min_size`
+fn lzw(pixels: &[u8], min_size: u32) -> Vec<u8> {
+    let clear = 1u32 << min_size;
+    let mut bits = Bits::default();
+    let mut size = min_size + 1;
+    let mut next = clear + 2;
+    // codes of the strings seen so far, by the code of their prefix and their last pixel
+    let mut table = std::collections::HashMap::new();
+
+    bits.write(clear, size);
+    let Some((&first, rest)) = pixels.split_first() else {
+        bits.write(clear + 1, size);
+        return bits.finish();
+    };
+
+    let mut prefix = first as u32;
+    for &pixel in rest {
+        if let Some(&code) = table.get(&(prefix, pixel)) {
+            prefix = code;
+            continue;
+        }
+
+        bits.write(prefix, size);
+        if next < 4096 {
+            table.insert((prefix, pixel), next);
+            next += 1;
+            if next > 1 << size && size < 12 {
+                size += 1;
+            }
+        } else {
+            // the table is full, so start over
+            bits.write(clear, size);
+            table.clear();
+            size = min_size + 1;
+            next = clear + 2;
+        }
+        prefix = pixel as u32;
+    }
+
+    bits.write(prefix, size);
+    bits.write(clear + 1, size);
+    bits.finish()
+}
+
+#[test]
+fn test_checksums() {
+    assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+    assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+    // the zlib header's check bits
+    assert_eq!(u16::from_be_bytes([0x78, 0x9c]) % 31, 0);
+}
+
+#[test]
+fn test_images() {
+    use super::Cell;
+
+    let mut board = Board::new([3, 2], Cell::DEAD);
+    board[[1, 0]] = Cell::ALIVE;
+    let mut render = Render::new(&Rule::default(), &Theme::default(), 2, None);
+    assert_eq!(render.palette, vec![[255, 255, 255], [0, 0, 0]]);
+    assert_eq!(render.pixels(&board)[..6], [0, 0, 1, 1, 0, 0]);
+
+    render.grid = Some([200, 200, 200]);
+    assert_eq!(render.size([3, 2]), (10, 7));
+    assert_eq!(render.pixels(&board)[10..20], [2, 0, 0, 2, 1, 1, 2, 0, 0, 2]);
+
+    let png = png(&board, &render);
+    assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+    assert_eq!(&png[12..16], b"IHDR");
+    assert_eq!(&png[png.len() - 8..png.len() - 4], b"IEND");
+
+    let animation = gif(&[board.clone(), board], &render, 10).unwrap();
+    assert_eq!(&animation[..6], b"GIF89a");
+    assert_eq!(animation.last(), Some(&0x3b));
+    assert!(gif(&[], &render, 10).is_err());
+}
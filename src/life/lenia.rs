@@ -0,0 +1,292 @@
+use dyn_array::DynArray;
+use std::fmt::Display;
+use std::path::Path;
+use std::str::FromStr;
+
+use super::{Board, Cell, Topology};
+
+/// Values of a continuous board, each in `[0, 1]`
+pub type Field = DynArray<f32, 2>;
+
+/// Cells of a Lenia board are drawn by rounding their value to one of this many levels
+pub const LEVELS: u8 = 10;
+
+/// Characters for each level, from empty to full
+pub const SHADES: [char; LEVELS as usize] = [' ', '.', ':', '-', '=', '+', '*', '#', '%', '@'];
+
+/// A Lenia rule: every cell grows or shrinks by how far the weighted sum of
+/// the cells in a ring around it is from `mu`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Lenia {
+    pub radius: usize,
+    /// Center of the growth function
+    pub mu: f32,
+    /// Width of the growth function
+    pub sigma: f32,
+    /// Time step, the fraction of the growth added each generation
+    pub dt: f32,
+    /// Heights of the rings of the kernel, from the innermost out
+    pub peaks: Vec<f32>,
+    /// Offsets and weights of the kernel, summing to 1
+    kernel: Vec<(isize, isize, f32)>,
+}
+
+impl Default for Lenia {
+    fn default() -> Self {
+        Lenia::new(13, 0.15, 0.015, 0.1, vec![1.0])
+    }
+}
+
+impl Lenia {
+    pub fn new(radius: usize, mu: f32, sigma: f32, dt: f32, peaks: Vec<f32>) -> Self {
+        let r = radius as isize;
+        let mut kernel = Vec::new();
+        for dy in -r..=r {
+            for dx in -r..=r {
+                let distance = ((dx * dx + dy * dy) as f32).sqrt() / radius as f32;
+                if distance >= 1.0 {
+                    continue;
+                }
+
+                // each ring is a smooth bump that is 0 at its edges and 1 in its middle
+                let scaled = distance * peaks.len() as f32;
+                let ring = (scaled as usize).min(peaks.len() - 1);
+                let t = scaled - ring as f32;
+                let bump = if t > 0.0 && t < 1.0 { (4.0 - 1.0 / (t * (1.0 - t))).exp() } else { 0.0 };
+
+                let weight = peaks[ring] * bump;
+                if weight > 0.0 {
+                    kernel.push((dx, dy, weight));
+                }
+            }
+        }
+
+        let total: f32 = kernel.iter().map(|&(_, _, w)| w).sum();
+        for (_, _, weight) in &mut kernel {
+            *weight /= total;
+        }
+
+        Lenia { radius, mu, sigma, dt, peaks, kernel }
+    }
+
+    /// Between -1 and 1, highest when `potential` is `mu`
+    pub fn growth(&self, potential: f32) -> f32 {
+        let d = (potential - self.mu) / self.sigma;
+        2.0 * (-d * d / 2.0).exp() - 1.0
+    }
+
+    pub fn step(&self, field: &Field, topology: Topology) -> Field {
+        let (width, height) = (field.width() as isize, field.height() as isize);
+        let mut next = field.clone();
+
+        for y in 0..height {
+            for x in 0..width {
+                let mut potential = 0.0;
+                for &(dx, dy, weight) in &self.kernel {
+                    let (mut nx, mut ny) = (x + dx, y + dy);
+                    if topology == Topology::Torus {
+                        nx = nx.rem_euclid(width);
+                        ny = ny.rem_euclid(height);
+                    } else if nx < 0 || ny < 0 || nx >= width || ny >= height {
+                        continue;
+                    }
+                    potential += weight * field[[nx as usize, ny as usize]];
+                }
+
+                let value = &mut next[[x as usize, y as usize]];
+                *value = (*value + self.dt * self.growth(potential)).clamp(0.0, 1.0);
+            }
+        }
+
+        next
+    }
+}
+
+/// The level `value` is drawn as
+pub fn level(value: f32) -> Cell {
+    Cell((value.clamp(0.0, 1.0) * (LEVELS - 1) as f32).round() as u8)
+}
+
+/// The value in the middle of the level of `cell`
+pub fn value(cell: Cell) -> f32 {
+    cell.0.min(LEVELS - 1) as f32 / (LEVELS - 1) as f32
+}
+
+pub fn quantize(field: &Field) -> Board {
+    Board::new_from_data(
+        [field.width(), field.height()],
+        field.data().iter().map(|&v| level(v)).collect(),
+    )
+}
+
+impl FromStr for Lenia {
+    type Err = String;
+
+    /// `lenia`, optionally followed by parameters as in `lenia:R=13,mu=0.15,sigma=0.015,dt=0.1,b=1;0.5`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let params = match s.split_once(':') {
+            Some((name, params)) if name.eq_ignore_ascii_case("lenia") => params,
+            None if s.eq_ignore_ascii_case("lenia") => "",
+            _ => return Err(format!("Invalid rule `{}`: expected lenia:R=..,mu=..,sigma=..,dt=..,b=..", s)),
+        };
+
+        let default = Lenia::default();
+        let (mut radius, mut mu, mut sigma, mut dt, mut peaks) =
+            (default.radius, default.mu, default.sigma, default.dt, default.peaks);
+
+        for param in params.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let (key, value) = param
+                .split_once('=')
+                .ok_or_else(|| format!("Invalid rule `{}`: expected `name=value` but found `{}`", s, param))?;
+            let number = |value: &str| -> Result<f32, String> {
+                value
+                    .trim()
+                    .parse::<f32>()
+                    .ok()
+                    .filter(|n| n.is_finite() && *n >= 0.0)
+                    .ok_or_else(|| format!("Invalid rule `{}`: `{}` isn't a positive number", s, value))
+            };
+
+            match key.trim() {
+                "R" | "r" => {
+                    radius = value
+                        .parse()
+                        .ok()
+                        .filter(|r| (1..=64).contains(r))
+                        .ok_or_else(|| format!("Invalid rule `{}`: the radius must be 1 to 64", s))?
+                }
+                "mu" | "m" => mu = number(value)?,
+                "sigma" | "s" => sigma = number(value)?,
+                "dt" => dt = number(value)?,
+                "b" => peaks = value.split(';').map(number).collect::<Result<_, _>>()?,
+                _ => return Err(format!("Invalid rule `{}`: unknown parameter `{}`", s, key)),
+            }
+        }
+
+        if sigma == 0.0 || peaks.is_empty() || peaks.iter().all(|&b| b == 0.0) {
+            return Err(format!("Invalid rule `{}`: sigma and at least one peak must be above 0", s));
+        }
+
+        Ok(Lenia::new(radius, mu, sigma, dt, peaks))
+    }
+}
+
+impl Display for Lenia {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let peaks: Vec<String> = self.peaks.iter().map(f32::to_string).collect();
+        write!(
+            f,
+            "lenia:R={},mu={},sigma={},dt={},b={}",
+            self.radius,
+            self.mu,
+            self.sigma,
+            self.dt,
+            peaks.join(";")
+        )
+    }
+}
+
+/// Writes the parameters of `lenia`, followed by the values of `field` if given
+pub fn save(path: &Path, lenia: &Lenia, field: Option<&Field>) -> Result<(), String> {
+    let mut out = format!("#R {}\n", lenia);
+
+    if let Some(field) = field {
+        out.push_str(&format!("#S {} {}\n", field.width(), field.height()));
+        out.push_str(&write_values(field));
+    }
+
+    std::fs::write(path, out).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Reads a file written by `save`. A file of only parameters loads an empty field
+pub fn load(path: &Path) -> Result<(Lenia, Option<Field>), String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let err = |e: &str| format!("Invalid Lenia file {}: {}", path.display(), e);
+
+    let mut lenia = None;
+    let mut size = None;
+    let mut values = String::new();
+    for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        if let Some(rule) = line.strip_prefix("#R") {
+            lenia = Some(rule.trim().parse().map_err(|e: String| err(&e))?);
+        } else if let Some(dims) = line.strip_prefix("#S") {
+            let dims: Vec<usize> = dims.split_whitespace().filter_map(|d| d.parse().ok()).collect();
+            match dims[..] {
+                [w, h] if w > 0 && h > 0 => size = Some((w, h)),
+                _ => return Err(err("expected `#S <width> <height>`")),
+            }
+        } else if !line.starts_with('#') {
+            values.push_str(line);
+            values.push('\n');
+        }
+    }
+
+    let lenia = lenia.ok_or_else(|| err("missing the `#R` line of parameters"))?;
+    let field = match size {
+        Some(size) => Some(read_values(&values, size).map_err(|e| err(&e))?),
+        None => None,
+    };
+
+    Ok((lenia, field))
+}
+
+/// The values of `field`, a row per line. Each is written exactly, so reading
+/// them back carries on just as `field` would
+pub fn write_values(field: &Field) -> String {
+    let mut out = String::new();
+    for row in field.data().chunks(field.width()) {
+        let values: Vec<String> = row.iter().map(|v| v.to_string()).collect();
+        out.push_str(&values.join(" "));
+        out.push('\n');
+    }
+    out
+}
+
+/// Reads a field of `width` by `height` values written by `write_values`
+pub fn read_values(text: &str, (width, height): (usize, usize)) -> Result<Field, String> {
+    let mut values = Vec::new();
+    for value in text.split_whitespace() {
+        values.push(value.parse::<f32>().map_err(|_| format!("bad value `{}`", value))?.clamp(0.0, 1.0));
+    }
+    if values.len() != width * height {
+        return Err(String::from("the number of values doesn't match the size"));
+    }
+    Ok(Field::new_from_data([width, height], values))
+}
+
+#[test]
+fn test_lenia() {
+    let lenia: Lenia = "lenia:R=5,mu=0.2,sigma=0.02,dt=0.5,b=1;0.5".parse().unwrap();
+    assert_eq!(lenia.to_string(), "lenia:R=5,mu=0.2,sigma=0.02,dt=0.5,b=1;0.5");
+    assert_eq!("lenia".parse::<Lenia>().unwrap(), Lenia::default());
+    assert!("lenia:R=0".parse::<Lenia>().is_err());
+    assert!("lenia:sigma=0".parse::<Lenia>().is_err());
+
+    let total: f32 = lenia.kernel.iter().map(|&(_, _, w)| w).sum();
+    assert!((total - 1.0).abs() < 1e-4);
+    assert!(lenia.kernel.iter().all(|&(dx, dy, _)| dx * dx + dy * dy < 25));
+    assert_eq!(lenia.growth(0.2), 1.0);
+    assert!(lenia.growth(0.0) < -0.99);
+
+    // an empty field stays empty and a full one decays
+    let empty = Field::new([12, 12], 0.0);
+    assert!(lenia.step(&empty, Topology::Torus).data().iter().all(|&v| v == 0.0));
+    let full = Field::new([12, 12], 1.0);
+    assert!(lenia.step(&full, Topology::Torus).data().iter().all(|&v| v == 0.5));
+
+    assert_eq!(level(1.0), Cell(LEVELS - 1));
+    assert_eq!(level(value(Cell(4))), Cell(4));
+
+    let path = std::env::temp_dir().join("game_of_life_test.lenia");
+    let mut field = Field::new([3, 2], 0.0);
+    field[[1, 0]] = 0.25;
+    field[[2, 1]] = 1.0;
+    save(&path, &lenia, Some(&field)).unwrap();
+    let (loaded, loaded_field) = load(&path).unwrap();
+    assert_eq!(loaded, lenia);
+    assert!(loaded_field.unwrap().data() == field.data());
+
+    save(&path, &lenia, None).unwrap();
+    assert!(load(&path).unwrap().1.is_none());
+    std::fs::remove_file(path).unwrap();
+}
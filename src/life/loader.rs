@@ -1,37 +1,66 @@
-use super::Board;
+use super::{Board, Cell};
 use std::mem::size_of;
 
-pub fn load(path: &str) -> Result<Board, std::io::Error> {
-    assert_eq!(size_of::<super::Cell>(), 1);
-    let bytes = std::fs::read(path)?;
-    let dims = from_bytes(&bytes[0..size_of::<[usize; 2]>()]);
+pub fn load(path: &str) -> Result<Board, String> {
+    decode(&std::fs::read(path).map_err(|e| e.to_string())?)
+}
 
-    let mut data_bytes = Vec::new();
-    for byte in &bytes[size_of::<[usize; 2]>()..] {
-        for bit in 0..8 {
-            data_bytes.push((byte >> bit) & 1);
-        }
-    }
+/// Reads a board written by `saver::encode`
+pub fn decode(bytes: &[u8]) -> Result<Board, String> {
+    let (multi_state, bytes) = match bytes.strip_prefix(super::saver::MULTI_STATE_MAGIC) {
+        Some(rest) => (true, rest),
+        None => (false, bytes),
+    };
+    // read a byte at a time, as the cells after the dims needn't be aligned for them
+    let (dims, data) = bytes
+        .split_at_checked(size_of::<[usize; 2]>())
+        .ok_or_else(|| String::from("Board save is too short to hold its size"))?;
+    let (width, height) = dims.split_at(size_of::<usize>());
+    let dims = [width, height].map(|dim| usize::from_ne_bytes(dim.try_into().unwrap()));
 
-    let data = from_bytes(&data_bytes);
-    Ok(Board::new_from_data([dims[0], dims[1]], Vec::from(&data[0..(dims[0] * dims[1])])))
-}
+    let len = dims[0].checked_mul(dims[1]).ok_or_else(|| String::from("Board save is too large"))?;
+    let needed = if multi_state { len } else { len.div_ceil(8) };
+    if data.len() < needed {
+        return Err(format!("Board save of {}x{} cells is cut short", dims[0], dims[1]));
+    }
 
-fn from_bytes<T>(x: &[u8]) -> &[T] {
-    assert_eq!(x.len() % size_of::<T>(), 0);
-    unsafe { std::slice::from_raw_parts(x.as_ptr() as *const T, x.len() / size_of::<T>()) }
+    let cells: Vec<Cell> = match multi_state {
+        true => data.iter().map(|&byte| Cell(byte)).collect(),
+        false => data.iter().flat_map(|byte| (0..8).map(move |bit| Cell((byte >> bit) & 1))).collect(),
+    };
+    Ok(Board::new_from_data(dims, Vec::from(&cells[0..len])))
 }
 
 #[test]
 fn test_load() {
-    let board = load("test1.dat").unwrap();
-    
+    let path = std::env::temp_dir().join("game_of_life_test_load.dat");
+    let mut saved = dyn_array::DynArray::new([5, 5], super::Cell::DEAD);
+    saved[[0, 0]] = super::Cell::ALIVE;
+    saved[[1, 1]] = super::Cell::ALIVE;
+    super::saver::save(path.to_str().unwrap(), &saved).unwrap();
+
+    let board = load(path.to_str().unwrap()).unwrap();
+    assert!(board.data() == saved.data());
+
+    saved[[2, 2]] = super::Cell(3);
+    super::saver::save(path.to_str().unwrap(), &saved).unwrap();
+    assert!(load(path.to_str().unwrap()).unwrap().data() == saved.data());
+
     for (_, cell) in &board {
-        print!("{} ", match cell {
-            super::Cell::Alive => 'O',
-            super::Cell::Dead => '_',
+        print!("{} ", match *cell {
+            super::Cell::ALIVE => 'O',
+            super::Cell::DEAD => '_',
+            _ => '+',
         });
     }
 
     println!();
+
+    // a cut short or garbled save is an error rather than a panic
+    let bytes = super::saver::encode(&saved);
+    assert!(decode(&bytes[..3]).is_err());
+    assert!(decode(&bytes[..bytes.len() - 1]).is_err());
+    let mut huge = usize::MAX.to_ne_bytes().to_vec();
+    huge.extend_from_slice(&2usize.to_ne_bytes());
+    assert!(decode(&huge).is_err());
 }
\ No newline at end of file
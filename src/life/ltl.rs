@@ -0,0 +1,205 @@
+use std::fmt::Display;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+
+use super::{Board, Cell, Topology};
+
+/// Shape of a Larger than Life neighborhood
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Range {
+    /// Every cell within `radius` in both directions
+    Moore,
+    /// Every cell within `radius` steps along the grid, a diamond
+    VonNeumann,
+}
+
+/// A Larger than Life rule such as `R5,C0,M1,S34..58,B34..45,NM` (Bosco's rule)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LargerThanLife {
+    pub radius: usize,
+    /// Number of cell states. `C0` and `C1` both mean 2
+    pub states: u8,
+    /// Whether a cell counts itself among its neighbors
+    pub middle: bool,
+    pub survival: RangeInclusive<usize>,
+    pub birth: RangeInclusive<usize>,
+    pub range: Range,
+}
+
+impl LargerThanLife {
+    /// The next generation of `board`. Neighbors are counted with a summed-area
+    /// table so each cell costs the same whatever the radius
+    pub fn step(&self, board: &Board, topology: Topology) -> Board {
+        let (width, height) = (board.width(), board.height());
+        let r = self.radius;
+
+        // alive cells of the board with a margin of `r` on every side, wrapped
+        // around on a torus and dead on a bounded board
+        let (pw, ph) = (width + 2 * r, height + 2 * r);
+        let alive = |px: usize, py: usize| -> usize {
+            let (x, y) = (px as isize - r as isize, py as isize - r as isize);
+            let (x, y) = match topology {
+                Topology::Torus => (x.rem_euclid(width as isize), y.rem_euclid(height as isize)),
+                Topology::Bounded if x < 0 || y < 0 || x >= width as isize || y >= height as isize => return 0,
+                Topology::Bounded => (x, y),
+            };
+            (board[[x as usize, y as usize]] == Cell::ALIVE) as usize
+        };
+
+        // sums[y][x] is the number of alive cells above and left of (x, y), and
+        // rows[y][x] the number left of it in row y
+        let mut sums = vec![0; (pw + 1) * (ph + 1)];
+        let mut rows = vec![0; (pw + 1) * ph];
+        for py in 0..ph {
+            for px in 0..pw {
+                let a = alive(px, py);
+                rows[py * (pw + 1) + px + 1] = rows[py * (pw + 1) + px] + a;
+                sums[(py + 1) * (pw + 1) + px + 1] =
+                    sums[py * (pw + 1) + px + 1] + rows[py * (pw + 1) + px + 1];
+            }
+        }
+
+        let rect = |x0: usize, y0: usize, x1: usize, y1: usize| {
+            sums[y1 * (pw + 1) + x1] + sums[y0 * (pw + 1) + x0] - sums[y0 * (pw + 1) + x1] - sums[y1 * (pw + 1) + x0]
+        };
+        let span = |py: usize, x0: usize, x1: usize| rows[py * (pw + 1) + x1] - rows[py * (pw + 1) + x0];
+
+        let mut next = board.clone();
+        for y in 0..height {
+            for x in 0..width {
+                // (x, y) is at (x + r, y + r) in the padded board
+                let mut count = match self.range {
+                    Range::Moore => rect(x, y, x + 2 * r + 1, y + 2 * r + 1),
+                    Range::VonNeumann => (0..=2 * r)
+                        .map(|dy| {
+                            let half = r - dy.abs_diff(r);
+                            span(y + dy, x + r - half, x + r + half + 1)
+                        })
+                        .sum(),
+                };
+
+                let cell = board[[x, y]];
+                if !self.middle && cell == Cell::ALIVE {
+                    count -= 1;
+                }
+
+                next[[x, y]] = match cell {
+                    Cell::DEAD if self.birth.contains(&count) => Cell::ALIVE,
+                    Cell::DEAD => Cell::DEAD,
+                    Cell::ALIVE if self.survival.contains(&count) => Cell::ALIVE,
+                    Cell(state) if state + 1 < self.states => Cell(state + 1),
+                    _ => Cell::DEAD,
+                };
+            }
+        }
+
+        next
+    }
+}
+
+impl FromStr for LargerThanLife {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = |e: &str| format!("Invalid rule `{}`: {}", s, e);
+        let (mut radius, mut states, mut middle, mut survival, mut birth, mut range) =
+            (None, 2, false, None, None, Range::Moore);
+
+        for part in s.split(',').map(str::trim) {
+            let (key, value) = part.split_at(part.chars().next().map_or(0, char::len_utf8));
+            match key.to_ascii_uppercase().as_str() {
+                "R" => radius = Some(value.parse().ok().filter(|&r| r >= 1).ok_or_else(|| err("bad radius"))?),
+                "C" => states = value.parse::<u8>().map_err(|_| err("bad number of states"))?.max(2),
+                "M" => {
+                    middle = match value {
+                        "0" => false,
+                        "1" => true,
+                        _ => return Err(err("M must be 0 or 1")),
+                    }
+                }
+                "S" => survival = Some(parse_range(value).ok_or_else(|| err("expected S<min>..<max>"))?),
+                "B" => birth = Some(parse_range(value).ok_or_else(|| err("expected B<min>..<max>"))?),
+                "N" => {
+                    range = match value.to_ascii_uppercase().as_str() {
+                        "M" => Range::Moore,
+                        "N" => Range::VonNeumann,
+                        _ => return Err(err("the neighborhood must be NM or NN")),
+                    }
+                }
+                _ => return Err(err(&format!("unexpected `{}`", part))),
+            }
+        }
+
+        Ok(LargerThanLife {
+            radius: radius.ok_or_else(|| err("missing the radius R"))?,
+            states,
+            middle,
+            survival: survival.ok_or_else(|| err("missing the survival range S"))?,
+            birth: birth.ok_or_else(|| err("missing the birth range B"))?,
+            range,
+        })
+    }
+}
+
+fn parse_range(range: &str) -> Option<RangeInclusive<usize>> {
+    let (min, max) = range.split_once("..")?;
+    Some(min.parse().ok()?..=max.parse().ok()?)
+}
+
+impl Display for LargerThanLife {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "R{},C{},M{},S{}..{},B{}..{},N{}",
+            self.radius,
+            if self.states > 2 { self.states } else { 0 },
+            self.middle as u8,
+            self.survival.start(),
+            self.survival.end(),
+            self.birth.start(),
+            self.birth.end(),
+            match self.range {
+                Range::Moore => 'M',
+                Range::VonNeumann => 'N',
+            }
+        )
+    }
+}
+
+#[test]
+fn test_larger_than_life() {
+    let bosco: LargerThanLife = "R5,C0,M1,S34..58,B34..45,NM".parse().unwrap();
+    assert_eq!(bosco.radius, 5);
+    assert_eq!(bosco.to_string(), "R5,C0,M1,S34..58,B34..45,NM");
+    assert!("R5,C0,M1,S34..58".parse::<LargerThanLife>().is_err());
+
+    // with radius 1 and the middle left out, this is plain life
+    let life: LargerThanLife = "R1,C0,M0,S2..3,B3..3,NM".parse().unwrap();
+    let mut board = Board::new([5, 5], Cell::DEAD);
+    for x in 1..4 {
+        board[[x, 2]] = Cell::ALIVE;
+    }
+    let next = life.step(&board, Topology::Bounded);
+    for y in 0..5 {
+        for x in 0..5 {
+            let alive = x == 2 && (1..4).contains(&y);
+            assert_eq!(next[[x, y]] == Cell::ALIVE, alive);
+        }
+    }
+
+    // compare the diamond of radius 2 against counting it directly
+    let diamond: LargerThanLife = "R2,C0,M1,S2..2,B2..2,NN".parse().unwrap();
+    let next = diamond.step(&board, Topology::Torus);
+    let count = |x: usize, y: usize| {
+        (0..5)
+            .flat_map(|dy| (0..5).map(move |dx| (dx, dy)))
+            .filter(|&(dx, dy)| (dx as isize - 2).abs() + (dy as isize - 2).abs() <= 2)
+            .filter(|&(dx, dy)| board[[(x + dx + 3) % 5, (y + dy + 3) % 5]] == Cell::ALIVE)
+            .count()
+    };
+    for y in 0..5 {
+        for x in 0..5 {
+            assert_eq!(next[[x, y]] == Cell::ALIVE, count(x, y) == 2, "{} {}", x, y);
+        }
+    }
+}
@@ -0,0 +1,185 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+use super::{Board, Cell, Topology};
+
+/// A block rule on the Margolus neighborhood: the board is split into 2x2
+/// blocks, each replaced as a whole through a table of the 16 possible blocks.
+/// The blocks shift by one cell diagonally every generation
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Margolus {
+    /// The block each block turns into, with its cells as bits: 1 top left,
+    /// 2 top right, 4 bottom left and 8 bottom right
+    pub table: [u8; 16],
+    /// Name of a built in rule, written instead of its table
+    name: Option<&'static str>,
+}
+
+/// Turns a block into the next, with its cells as bits as in `Margolus::table`
+type BlockRule = fn(u8) -> u8;
+
+/// Built in rules by name
+const NAMED: [(&str, BlockRule); 3] = [
+    // a block with two alive cells stays, the rest are inverted, and those
+    // that had three alive cells are also turned around
+    ("critters", |block| match block.count_ones() {
+        2 => block,
+        3 => rotate(!block & 15),
+        _ => !block & 15,
+    }),
+    // a lone ball moves to the opposite corner, and two balls meeting on a
+    // diagonal bounce off onto the other one
+    ("bbm", |block| match block {
+        1 | 2 | 4 | 8 => rotate(block),
+        6 => 9,
+        9 => 6,
+        _ => block,
+    }),
+    // blocks all of one state are inverted
+    ("tron", |block| match block {
+        0 => 15,
+        15 => 0,
+        _ => block,
+    }),
+];
+
+/// A block turned by 180 degrees, which swaps opposite corners
+fn rotate(block: u8) -> u8 {
+    (0..4).filter(|bit| block & 1 << bit != 0).fold(0, |b, bit| b | 1 << (3 - bit))
+}
+
+impl Margolus {
+    /// The rule undoing this one, if no two blocks turn into the same block
+    pub fn inverse(&self) -> Option<Margolus> {
+        let mut table = [16; 16];
+        for (block, &next) in self.table.iter().enumerate() {
+            if table[next as usize] != 16 {
+                return None;
+            }
+            table[next as usize] = block as u8;
+        }
+
+        Some(Margolus { table, name: None })
+    }
+
+    /// The generation after `board`, with blocks starting at even cells for
+    /// `phase` 0 and odd cells for `phase` 1. Blocks that stick out past the
+    /// edge of a bounded board, or of a torus with an odd size, are left as they are
+    pub fn step(&self, board: &Board, topology: Topology, phase: u8) -> Board {
+        let (width, height) = (board.width(), board.height());
+        let offset = phase as usize % 2;
+        let wrap = |i: usize, len: usize| match i {
+            i if i < len => Some(i),
+            i if topology == Topology::Torus && len.is_multiple_of(2) => Some(i % len),
+            _ => None,
+        };
+
+        let mut next = board.clone();
+        for y in (offset..height + offset).step_by(2) {
+            for x in (offset..width + offset).step_by(2) {
+                let (Some(x1), Some(y1)) = (wrap(x + 1, width), wrap(y + 1, height)) else {
+                    continue;
+                };
+                let (Some(x), Some(y)) = (wrap(x, width), wrap(y, height)) else {
+                    continue;
+                };
+
+                let cells = [[x, y], [x1, y], [x, y1], [x1, y1]];
+                let block = cells
+                    .iter()
+                    .enumerate()
+                    .filter(|&(_, &i)| board[i] == Cell::ALIVE)
+                    .fold(0, |block, (bit, _)| block | 1 << bit);
+
+                let block = self.table[block as usize];
+                for (bit, i) in cells.into_iter().enumerate() {
+                    next[i] = if block & 1 << bit != 0 { Cell::ALIVE } else { Cell::DEAD };
+                }
+            }
+        }
+
+        next
+    }
+}
+
+impl FromStr for Margolus {
+    type Err = String;
+
+    /// `critters`, `bbm` (the billiard ball machine), `tron`, or `margolus:`
+    /// followed by the 16 entries of a table such as `margolus:15,14,13,3,11,5,6,1,7,9,10,2,12,4,8,0`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let spec = s.strip_prefix("margolus:").unwrap_or(s).to_ascii_lowercase();
+
+        if let Some(&(name, next)) = NAMED.iter().find(|(name, _)| *name == spec) {
+            let table = std::array::from_fn(|block| next(block as u8));
+            return Ok(Margolus { table, name: Some(name) });
+        }
+
+        let entries: Vec<u8> = spec
+            .split(',')
+            .map(|entry| entry.trim().parse().ok().filter(|&e| e < 16))
+            .collect::<Option<_>>()
+            .ok_or_else(|| format!("Invalid rule `{}`: table entries must be 0 to 15", s))?;
+        let table = entries
+            .try_into()
+            .map_err(|_| format!("Invalid rule `{}`: expected critters, bbm, tron or 16 table entries", s))?;
+
+        Ok(Margolus { table, name: None })
+    }
+}
+
+impl Display for Margolus {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if let Some(name) = self.name {
+            return write!(f, "{}", name);
+        }
+
+        let entries: Vec<String> = self.table.iter().map(u8::to_string).collect();
+        write!(f, "margolus:{}", entries.join(","))
+    }
+}
+
+#[test]
+fn test_margolus() {
+    let bbm: Margolus = "bbm".parse().unwrap();
+    assert_eq!(bbm.to_string(), "bbm");
+    assert_eq!(bbm.table[1], 8);
+
+    // a ball moves diagonally, one block each generation
+    let mut board = Board::new([6, 6], Cell::DEAD);
+    board[[0, 0]] = Cell::ALIVE;
+    let board = bbm.step(&board, Topology::Bounded, 0);
+    assert_eq!(board[[1, 1]], Cell::ALIVE);
+    let board = bbm.step(&board, Topology::Bounded, 1);
+    assert_eq!(board[[2, 2]], Cell::ALIVE);
+    assert_eq!(board.data().iter().filter(|&&c| c == Cell::ALIVE).count(), 1);
+
+    // on a bounded board the odd phase leaves the edges alone
+    let tron: Margolus = "margolus:tron".parse().unwrap();
+    let board = tron.step(&Board::new([4, 4], Cell::DEAD), Topology::Bounded, 1);
+    assert_eq!(board[[1, 1]], Cell::ALIVE);
+    assert_eq!(board[[0, 0]], Cell::DEAD);
+    let board = tron.step(&Board::new([4, 4], Cell::DEAD), Topology::Torus, 1);
+    assert!(board.data().iter().all(|&c| c == Cell::ALIVE));
+
+    // critters is reversible: running its inverse backwards gives the start again
+    let critters: Margolus = "critters".parse().unwrap();
+    let inverse = critters.inverse().unwrap();
+    let mut start = Board::new([8, 6], Cell::DEAD);
+    for (i, cell) in start.data_mut().iter_mut().enumerate() {
+        *cell = if i * 7 % 5 < 2 { Cell::ALIVE } else { Cell::DEAD };
+    }
+    let mut board = start.clone();
+    for phase in 0..6 {
+        board = critters.step(&board, Topology::Torus, phase % 2);
+    }
+    for phase in (0..6).rev() {
+        board = inverse.step(&board, Topology::Torus, phase % 2);
+    }
+    assert!(board.data() == start.data());
+
+    let custom: Margolus = "margolus:15,14,13,3,11,5,6,1,7,9,10,2,12,4,8,0".parse().unwrap();
+    assert_eq!(custom.to_string(), "margolus:15,14,13,3,11,5,6,1,7,9,10,2,12,4,8,0");
+    assert!("margolus:1,2,3".parse::<Margolus>().is_err());
+    assert!("margolus:0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0".parse::<Margolus>().unwrap().inverse().is_none());
+}
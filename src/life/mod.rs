@@ -1,16 +1,62 @@
 use std::fmt::Display;
+use std::str::FromStr;
+use crossterm::style::{Color, SetBackgroundColor, SetForegroundColor};
 use dyn_array::DynArray;
-
+use chance::Chance;
+use rule::{Neighborhood, Rule};
+use soup::Soup;
+use theme::Theme;
+use turmite::Ant;
+
+pub mod chance;
+pub mod colored;
+pub mod elementary;
+pub mod formats;
+pub mod history;
+pub mod image;
+pub mod lenia;
 pub mod loader;
+pub mod ltl;
+pub mod margolus;
+pub mod rule;
 pub mod saver;
+pub mod soup;
+pub mod svg;
+pub mod table;
+pub mod theme;
+pub mod turmite;
 
 pub struct Life {
     pub cursor_pos: Pos,
-    pub initial_cursor_pos: Option<(u16, u16)>,
     pub dead_cell: char,
     pub alive_cell: char,
+    /// Character drawn for the refractory states of Generations rules
+    pub dying_cell: char,
+    /// Characters drawn for each state, overriding the ones above
+    pub state_chars: Vec<char>,
     pub board: Board,
+    pub rule: Rule,
+    pub topology: Topology,
+    pub theme: Theme,
+    /// The random fill the board was created with
+    pub soup: Option<Soup>,
+    /// Pattern the board was loaded from or made with, kept with snapshots of its runs
+    pub parent: Option<String>,
+    /// Ants of a turmite rule
+    pub ants: Vec<Ant>,
+    /// Chances and noise making the tick random
+    pub chance: Option<Chance>,
+    /// Exact values of the cells of a Lenia rule, which `board` holds rounded
+    field: Option<lenia::Field>,
     inital_state: Board,
+    inital_ants: Vec<Ant>,
+    inital_field: Option<lenia::Field>,
+    /// Generations each alive cell has been alive for, or each dead cell has been dead for
+    ages: DynArray<u16, 2>,
+    /// Row holding the latest generation of a one dimensional rule
+    row: usize,
+    /// Which of the two block partitions the next tick of a Margolus rule uses
+    phase: u8,
     dead: bool,
 }
 
@@ -28,10 +74,12 @@ pub mod prefab {
 
     #[derive(Copy, Clone, Debug)]
     pub enum PrefabPlaceError {
+        #[allow(dead_code)] // only read through `Debug` when reporting the failure
         OutOfBounds(bool, bool),
         CellOverlap,
     }
 
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
     pub enum Rotation {
         Up,
         Down,
@@ -43,14 +91,14 @@ pub mod prefab {
         RightFlipped,
     }
 
-    pub fn load_prefabs() -> Vec<Prefab> {
-        if !path::Path::new("./prefabs/").exists() {
+    pub fn load_prefabs(dir: &path::Path) -> Vec<Prefab> {
+        if !dir.exists() {
             return Vec::new();
         };
 
         let mut prefabs = Vec::new();
 
-        for prefab in std::fs::read_dir("./prefabs/").unwrap() {
+        for prefab in std::fs::read_dir(dir).unwrap() {
             let prefab = prefab.unwrap();
             prefabs.push(
                 Prefab {
@@ -75,37 +123,46 @@ pub mod prefab {
     }
 }
 
-pub enum Cell {
-    Dead,
-    Alive,
+/// State of a cell. `0` is dead and `1` is alive, with any higher states
+/// being the refractory (dying) states of a Generations rule or the states of a rule table
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[repr(transparent)]
+pub struct Cell(pub u8);
+
+impl Cell {
+    pub const DEAD: Cell = Cell(0);
+    pub const ALIVE: Cell = Cell(1);
 }
 
-impl PartialEq for Cell {
-    fn eq(&self, other: &Cell) -> bool {
-        match self {
-            Cell::Dead => match other {
-                Cell::Dead => true,
-                Cell::Alive => false,
-            },
-            Cell::Alive => match other {
-                Cell::Dead => false,
-                Cell::Alive => true,
-            },
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Topology {
+    /// Cells past the edge of the board are always dead
+    Bounded,
+    /// The board wraps around at its edges
+    Torus,
+}
+
+impl FromStr for Topology {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bounded" | "plane" => Ok(Topology::Bounded),
+            "torus" | "toroidal" | "wrap" => Ok(Topology::Torus),
+            _ => Err(format!("Unknown topology `{}`, expected bounded or torus", s)),
         }
     }
 }
 
-impl Clone for Cell {
-    fn clone(&self) -> Self {
+impl Display for Topology {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
-            Cell::Dead => Cell::Dead,
-            Cell::Alive => Cell::Alive,
+            Topology::Bounded => write!(f, "bounded"),
+            Topology::Torus => write!(f, "torus"),
         }
     }
 }
 
-impl Copy for Cell {}
-
 #[derive(Clone, Copy, Debug)]
 pub struct Pos {
     pub x: usize,
@@ -117,7 +174,7 @@ impl Life {
         board_dims: (usize, usize),
         dead_cell: char,
         alive_cell: char,
-        is_rand: bool,
+        soup: Option<Soup>,
         board: Option<Board>,
     ) -> Self {
         let (w, h) = board_dims;
@@ -126,14 +183,28 @@ impl Life {
             board: if let Some(board) = board {
                 board
             } else {
-                Life::init_board(Cell::Dead, [w, h], is_rand)
+                Life::init_board(Cell::DEAD, [w, h], soup.as_ref())
             },
-            inital_state: Life::init_board(Cell::Dead, [w, h], false),
+            soup,
+            parent: None,
+            rule: Rule::default(),
+            topology: Topology::Bounded,
+            theme: Theme::default(),
+            ants: Vec::new(),
+            chance: None,
+            field: None,
+            inital_state: Life::init_board(Cell::DEAD, [w, h], None),
+            inital_ants: Vec::new(),
+            inital_field: None,
+            ages: DynArray::new([w, h], 0),
+            row: 0,
+            phase: 0,
             dead_cell,
             alive_cell,
+            dying_cell: '+',
+            state_chars: Vec::new(),
             dead: false,
             cursor_pos: Pos { x: 0, y: 0 },
-            initial_cursor_pos: None
         }
     }
 
@@ -141,12 +212,52 @@ impl Life {
         for (i, cell) in &mut self.inital_state {
             *cell = self.board[i];
         }
+        self.ages.data_mut().fill(0);
+
+        // a turmite starts with one ant in the middle if none were placed
+        if matches!(self.rule, Rule::Turmite(_)) && self.ants.is_empty() {
+            self.ants.push(Ant::new(self.board.width() / 2, self.board.height() / 2));
+        }
+        self.inital_ants = self.ants.clone();
+        self.inital_field = self.field.clone();
+        if let Some(chance) = &mut self.chance {
+            chance.reseed();
+        }
+    }
+
+    /// Grows or shrinks the board to `dims`, keeping the cells still on it
+    /// along with the state the run started from, and keeps the cursor on it
+    pub fn resize(&mut self, dims: (usize, usize)) {
+        let (w, h) = dims;
+        self.board = resized(&self.board, dims, Cell::DEAD);
+        self.inital_state = resized(&self.inital_state, dims, Cell::DEAD);
+        self.ages = resized(&self.ages, dims, 0);
+        self.field = self.field.as_ref().map(|field| resized(field, dims, 0.0));
+        self.inital_field = self.inital_field.as_ref().map(|field| resized(field, dims, 0.0));
+        self.ants.retain(|ant| ant.x < w && ant.y < h);
+        self.inital_ants.retain(|ant| ant.x < w && ant.y < h);
+
+        self.row = self.row.min(h - 1);
+        self.cursor_pos = Pos { x: self.cursor_pos.x.min(w - 1), y: self.cursor_pos.y.min(h - 1) };
+    }
+
+    /// The board the last run started from
+    pub fn initial_state(&self) -> &Board {
+        &self.inital_state
     }
 
     pub fn load_inital(&mut self) {
         for (i, cell) in &mut self.board {
             *cell = self.inital_state[i];
         }
+        self.ages.data_mut().fill(0);
+        self.row = 0;
+        self.phase = 0;
+        self.ants = self.inital_ants.clone();
+        self.field = self.inital_field.clone();
+        if let Some(chance) = &mut self.chance {
+            chance.reseed();
+        }
     }
 
     pub fn reset(&mut self) {
@@ -154,17 +265,11 @@ impl Life {
         self.dead = false;
     }
 
-    fn init_board(init_cell: Cell, dims: [usize; 2], random: bool) -> Board {
+    fn init_board(init_cell: Cell, dims: [usize; 2], soup: Option<&Soup>) -> Board {
         let mut cells = Board::new(dims, init_cell);
 
-        if random {
-            for cell in cells.data_mut() {
-                *cell = if rand::random() {
-                    Cell::Alive
-                } else {
-                    Cell::Dead
-                }
-            }
+        if let Some(soup) = soup {
+            soup.fill(&mut cells);
         }
 
         cells
@@ -173,9 +278,9 @@ impl Life {
     pub fn toggle_cell(&mut self, pos: Pos) -> Result<Cell, ()> {
         self.set_cell(
             pos,
-            match Life::get_board_cell(pos, &self.board).unwrap_or(Cell::Dead) {
-                Cell::Dead => Cell::Alive,
-                Cell::Alive => Cell::Dead,
+            match Life::get_board_cell(pos, &self.board).unwrap_or(Cell::DEAD) {
+                // cycles through every state of the rule
+                Cell(state) => Cell((state + 1) % self.rule.states()),
             },
         )
     }
@@ -209,82 +314,208 @@ impl Life {
             return;
         }
 
-        if self
-            .board
-            .into_iter()
-            .filter(|&(_, cell)| *cell == Cell::Alive)
-            .count()
-            == 0
-        {
+        if let Rule::Turmite(turmite) = &self.rule {
+            // only the cells under the ants change
+            let mut painted = Vec::with_capacity(self.ants.len());
+            let (board, topology) = (&mut self.board, self.topology);
+            self.ants.retain_mut(|ant| {
+                painted.push(([ant.x, ant.y], board[[ant.x, ant.y]]));
+                turmite.step(ant, board, topology)
+            });
+            for (i, before) in painted {
+                self.age_cell(i, before);
+            }
+
+            self.dead = self.ants.is_empty();
+            return;
+        }
+
+        // noise can bring an empty board back to life, as can block rules that fill empty blocks
+        let noisy = self.chance.as_ref().is_some_and(|chance| chance.noise > 0.0);
+        let revives = matches!(&self.rule, Rule::Margolus(rule) if rule.table[0] != 0);
+        if !noisy && !revives && self.board.data().iter().all(|&cell| cell == Cell::DEAD) {
             self.dead = true;
             return;
         }
 
-        let mut new_board =
-            Life::init_board(Cell::Dead, [self.board.width(), self.board.height()], false);
+        let mut new_board = match &self.rule {
+            Rule::Larger(rule) => rule.step(&self.board, self.topology),
+            Rule::Margolus(rule) => {
+                let board = rule.step(&self.board, self.topology, self.phase);
+                self.phase ^= 1;
+                board
+            }
+            Rule::Lenia(lenia) => {
+                let lenia = lenia.clone();
+                let field = lenia.step(&self.field(), self.topology);
+                let board = lenia::quantize(&field);
+                self.field = Some(field);
+                board
+            }
+            Rule::Elementary(rule) => {
+                let rule = *rule;
+                self.tick_row(rule);
+                return;
+            }
+            rule => {
+                let mut new_board =
+                    Life::init_board(Cell::DEAD, [self.board.width(), self.board.height()], None);
+                let neighborhood = rule.neighborhood();
+
+                for (i, cell) in &self.board {
+                    let mut neighbors = Life::neighbors(Pos { x: i[0], y: i[1] }, &self.board, self.topology);
+                    for (index, neighbor) in neighbors.iter_mut().enumerate() {
+                        if neighborhood.ignores(index, i[1]) {
+                            *neighbor = Cell::DEAD;
+                        }
+                    }
 
-        for (i, cell) in &self.board {
-            let alive = Life::alive_neighbors(Pos { x: i[0], y: i[1] }, &self.board);
+                    new_board[i] = rule.next(*cell, &neighbors);
 
-            new_board[i] = match cell {
-                Cell::Dead => {
-                    if alive == 3 {
-                        Cell::Alive
-                    } else {
-                        Cell::Dead
-                    }
-                }
-                Cell::Alive => {
-                    if alive == 2 || alive == 3 {
-                        Cell::Alive
-                    } else {
-                        Cell::Dead
+                    // chances only override the counts of life-like rules
+                    if let (Rule::LifeLike(life), Some(chance)) = (rule, &mut self.chance) {
+                        let count = neighbors.iter().filter(|&&n| n == Cell::ALIVE).count();
+                        new_board[i] = chance.decide(*cell, count, new_board[i], life.states);
                     }
                 }
+
+                new_board
             }
+        };
+
+        if let Some(chance) = &mut self.chance {
+            chance.add_noise(&mut new_board);
         }
 
-        self.board = new_board;
+        let before = std::mem::replace(&mut self.board, new_board);
+        self.update_ages(&before);
     }
 
-    fn alive_neighbors(pos: Pos, board: &Board) -> usize {
-        let at_pos = |x, y| Life::get_board_cell(Pos { x, y }, board).unwrap_or(Cell::Dead);
+    /// Steps a reversible Margolus rule one generation back, which also works
+    /// past the generation the run started from
+    pub fn reverse(&mut self) -> Result<(), String> {
+        let inverse = match &self.rule {
+            Rule::Margolus(rule) => rule.inverse().ok_or_else(|| format!("{} isn't reversible", rule))?,
+            rule => return Err(format!("{} isn't a reversible block rule", rule)),
+        };
 
-        let mut neighbors = [Cell::Dead; 8];
-        if pos.x < board.width() - 1 && pos.y < board.height() - 1 {
-            neighbors[0] = at_pos(pos.x + 1, pos.y + 1);
-        }
-        if pos.y < board.height() - 1 {
-            neighbors[1] = at_pos(pos.x, pos.y + 1);
-        }
-        if pos.x > 0 && pos.y < board.height() - 1 {
-            neighbors[2] = at_pos(pos.x - 1, pos.y + 1);
+        self.phase ^= 1;
+        let new_board = inverse.step(&self.board, self.topology, self.phase);
+        let before = std::mem::replace(&mut self.board, new_board);
+        self.update_ages(&before);
+        self.dead = false;
+        Ok(())
+    }
+
+    /// The values of a Lenia board, with any cells changed since the last tick
+    /// set to the middle of their new level
+    pub fn field(&self) -> lenia::Field {
+        let mut field = match &self.field {
+            Some(field) if field.width() == self.board.width() && field.height() == self.board.height() => field.clone(),
+            _ => lenia::Field::new([self.board.width(), self.board.height()], 0.0),
+        };
+
+        for (i, value) in &mut field {
+            if lenia::level(*value) != self.board[i] {
+                *value = lenia::value(self.board[i]);
+            }
         }
-        if pos.x < board.width() - 1 {
-            neighbors[3] = at_pos(pos.x + 1, pos.y);
+
+        field
+    }
+
+    /// Refills a random Lenia board with random values, or a colored one with
+    /// random teams, rather than the alive cells `new` filled it with before the
+    /// rule was known
+    pub fn refill_soup(&mut self) {
+        match (&self.rule, self.soup) {
+            (Rule::Lenia(_), Some(soup)) => {
+                let mut field = lenia::Field::new([self.board.width(), self.board.height()], 0.0);
+                soup.fill_field(&mut field);
+                self.set_field(field);
+            }
+            (Rule::Colored(rule), Some(soup)) => soup.fill_teams(&mut self.board, rule.teams),
+            _ => {}
         }
-        if pos.x > 0 {
-            neighbors[4] = at_pos(pos.x - 1, pos.y);
+    }
+
+    /// Sets the exact values of a Lenia board, and the board to match
+    pub fn set_field(&mut self, field: lenia::Field) {
+        self.board = lenia::quantize(&field);
+        self.field = Some(field);
+    }
+
+    /// Counts on the age of every cell, from the board `before` the last tick
+    fn update_ages(&mut self, before: &Board) {
+        for (i, cell) in before {
+            self.age_cell(i, *cell);
         }
-        if pos.x < board.width() - 1 && pos.y > 0 {
-            neighbors[5] = at_pos(pos.x + 1, pos.y - 1);
+    }
+
+    /// Counts on the age of the cell at `i`, which was `before` the last tick
+    fn age_cell(&mut self, i: [usize; 2], before: Cell) {
+        let next_alive = self.board[i] == Cell::ALIVE;
+
+        // an age of 0 means the cell hasn't been alive since the run started
+        let age = &mut self.ages[i];
+        *age = match (before == Cell::ALIVE, next_alive) {
+            (true, true) | (false, false) if *age > 0 => age.saturating_add(1),
+            (false, false) => 0,
+            _ => 1,
+        };
+    }
+
+    /// Places an ant facing north at `pos`, or removes the one there
+    pub fn toggle_ant(&mut self, pos: Pos) {
+        match self.ants.iter().position(|ant| ant.x == pos.x && ant.y == pos.y) {
+            Some(i) => {
+                self.ants.remove(i);
+            }
+            None => self.ants.push(Ant::new(pos.x, pos.y)),
         }
-        if pos.y > 0 {
-            neighbors[6] = at_pos(pos.x, pos.y - 1);
+    }
+
+    /// The eight cells around `pos` in N, NE, E, SE, S, SW, W, NW order.
+    /// Cells past the edge of a bounded board are dead
+    fn neighbors(pos: Pos, board: &Board, topology: Topology) -> [Cell; 8] {
+        let (width, height) = (board.width() as isize, board.height() as isize);
+        let offsets = [(0, -1), (1, -1), (1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0), (-1, -1)];
+
+        let mut neighbors = [Cell::DEAD; 8];
+        for (neighbor, (dx, dy)) in neighbors.iter_mut().zip(offsets) {
+            let (mut x, mut y) = (pos.x as isize + dx, pos.y as isize + dy);
+            if topology == Topology::Torus {
+                x = x.rem_euclid(width);
+                y = y.rem_euclid(height);
+            } else if x < 0 || y < 0 || x >= width || y >= height {
+                continue;
+            }
+
+            *neighbor = board[[x as usize, y as usize]];
         }
-        if pos.x > 0 && pos.y > 0 {
-            neighbors[7] = at_pos(pos.x - 1, pos.y - 1);
+
+        neighbors
+    }
+
+    /// Fills the row after the latest generation of a one dimensional rule,
+    /// scrolling the board up once it reaches the bottom
+    fn tick_row(&mut self, rule: elementary::Elementary) {
+        let width = self.board.width();
+        if self.row == 0 {
+            // only the top row is the initial generation
+            self.board.data_mut()[width..].fill(Cell::DEAD);
         }
 
-        let mut count = 0;
-        for i in neighbors {
-            match i {
-                Cell::Dead => {}
-                Cell::Alive => count += 1,
-            }
+        let current = self.board.data()[self.row * width..(self.row + 1) * width].to_vec();
+        let next = rule.next_row(&current, self.topology == Topology::Torus);
+
+        if self.row + 1 < self.board.height() {
+            self.row += 1;
+        } else {
+            self.board.data_mut().copy_within(width.., 0);
         }
 
-        count
+        self.board.data_mut()[self.row * width..(self.row + 1) * width].copy_from_slice(&next);
     }
 
     fn get_board_cell(pos: Pos, board: &Board) -> Option<Cell> {
@@ -320,8 +551,8 @@ impl Life {
             }
         };
 
-        let check_x = self.cursor_pos.x + width >= self.board.width() + 1;
-        let check_y = self.cursor_pos.y + height >= self.board.height() + 1;
+        let check_x = self.cursor_pos.x + width > self.board.width();
+        let check_y = self.cursor_pos.y + height > self.board.height();
 
         if check_x || check_y {
             return Err(PrefabPlaceError::OutOfBounds(check_x, check_y));
@@ -329,17 +560,17 @@ impl Life {
 
         for x in self.cursor_pos.x..self.cursor_pos.x + width {
             for y in self.cursor_pos.y..self.cursor_pos.y + height {
-                if Life::get_board_cell(Pos { x, y }, &self.board).unwrap_or(Cell::Dead) == Cell::Alive
+                if Life::get_board_cell(Pos { x, y }, &self.board).unwrap_or(Cell::DEAD) != Cell::DEAD
                 {
                     return Err(PrefabPlaceError::CellOverlap);
                 }
             }
         }
 
-        for pos in Life::rotate_prefab(prefab, rot) {
+        for (pos, cell) in Life::rotate_prefab(prefab, rot) {
             self.set_cell(
                 Pos { x: self.cursor_pos.x + pos.x, y: self.cursor_pos.y + pos.y },
-                Cell::Alive,
+                cell,
             )
             .unwrap();
         }
@@ -347,7 +578,7 @@ impl Life {
         Ok(())
     }
 
-    fn rotate_prefab(prefab: &Board, rot: prefab::Rotation) -> Vec<Pos> {
+    fn rotate_prefab(prefab: &Board, rot: prefab::Rotation) -> Vec<(Pos, Cell)> {
         let mut rotated_prefab = Vec::with_capacity(prefab.width() * prefab.height());
 
         for ([x, y], cell) in prefab {
@@ -362,8 +593,8 @@ impl Life {
                 prefab::Rotation::RightFlipped => Pos { x, y: prefab.height() - 1 - y },
             };
 
-            if let Cell::Alive = cell {
-                rotated_prefab.push(coords);
+            if *cell != Cell::DEAD {
+                rotated_prefab.push((coords, *cell));
             }
         }
 
@@ -374,9 +605,113 @@ impl Life {
         (self.board.width(), self.board.height())
     }
 
+    /// Columns row `y` is drawn shifted right by, which is one for the odd rows
+    /// of a hexagonal neighborhood
+    pub fn row_offset(&self, y: usize) -> u16 {
+        (self.rule.neighborhood() == Neighborhood::Hexagonal && y % 2 == 1) as u16
+    }
+
     pub fn is_dead(&self) -> bool {
         self.dead
     }
+
+    /// The character drawn for `cell`, with its color if one is set
+    pub fn cell_str(&self, cell: Cell) -> String {
+        self.styled_cell(cell, 0)
+    }
+
+    /// What is drawn at `pos`: the glyph of an ant standing there, or else the cell
+    pub fn pos_str(&self, pos: Pos) -> String {
+        match self.ants.iter().find(|ant| ant.x == pos.x && ant.y == pos.y) {
+            Some(ant) => self.ant_str(ant),
+            None => self.cell_str(self.board[[pos.x, pos.y]]),
+        }
+    }
+
+    /// The glyph of `ant`, drawn over the cell it stands on
+    fn ant_str(&self, ant: &Ant) -> String {
+        format!("{}{}{}", SetForegroundColor(Color::Red), ant.glyph(), SetForegroundColor(Color::Reset))
+    }
+
+    fn styled_cell(&self, cell: Cell, age: u16) -> String {
+        let (c, foreground, background) = match cell {
+            Cell::DEAD => (self.dead_cell, self.theme.dead, self.theme.trail_background(age)),
+            Cell::ALIVE => (self.alive_cell, self.theme.alive_color(age), None),
+            // every team is alive, in its own color
+            Cell(state) if state <= self.rule.teams() => (self.alive_cell, None, None),
+            Cell(state) => (self.dying_cell, self.theme.dying_color(state, self.rule.states()), None),
+        };
+
+        // Lenia cells are shaded by their value, brighter when fuller
+        let (c, foreground) = match self.rule {
+            Rule::Lenia(_) if cell != Cell::DEAD => (
+                lenia::SHADES[cell.0.min(lenia::LEVELS - 1) as usize],
+                self.theme.dying_color(lenia::LEVELS - cell.0.min(lenia::LEVELS - 1), lenia::LEVELS),
+            ),
+            _ => (c, foreground),
+        };
+
+        // rule tables name their own colors for each state
+        let state = cell.0 as usize;
+        let c = self.state_chars.get(state).copied().unwrap_or(c);
+        let foreground = self.rule.colors().get(state).copied().flatten().or(foreground);
+
+        let mut out = String::new();
+        if let Some(background) = background {
+            out.push_str(&SetBackgroundColor(background).to_string());
+        }
+        if let Some(foreground) = foreground {
+            out.push_str(&SetForegroundColor(foreground).to_string());
+        }
+
+        out.push(c);
+
+        if foreground.is_some() {
+            out.push_str(&SetForegroundColor(Color::Reset).to_string());
+        }
+        if background.is_some() {
+            let restore = self.theme.background.unwrap_or(Color::Reset);
+            out.push_str(&SetBackgroundColor(restore).to_string());
+        }
+
+        out
+    }
+
+    pub fn population(&self) -> usize {
+        match self.rule.teams() {
+            0 => self.board.data().iter().filter(|&&cell| cell == Cell::ALIVE).count(),
+            _ => self.team_populations().iter().sum(),
+        }
+    }
+
+    /// Population of each team of a colored rule, starting with the first, or
+    /// nothing for other rules
+    pub fn team_populations(&self) -> Vec<usize> {
+        let mut populations = vec![0; self.rule.teams() as usize];
+        if populations.is_empty() {
+            return populations;
+        }
+
+        for cell in self.board.data().iter().filter(|&&cell| cell != Cell::DEAD) {
+            if let Some(population) = populations.get_mut(cell.0 as usize - 1) {
+                *population += 1;
+            }
+        }
+
+        populations
+    }
+}
+
+/// `array` grown or shrunk to `dims`, keeping the values still inside and
+/// filling the rest with `fill`
+fn resized<T: Copy>(array: &DynArray<T, 2>, dims: (usize, usize), fill: T) -> DynArray<T, 2> {
+    let mut new = DynArray::new([dims.0, dims.1], fill);
+    for ([x, y], value) in array {
+        if x < dims.0 && y < dims.1 {
+            new[[x, y]] = *value;
+        }
+    }
+    new
 }
 
 impl Display for Life {
@@ -389,20 +724,30 @@ impl Display for Life {
         }
         output.push_str(" -\n\r|");
 
-        // cells and side `|`
-        for (i, cell) in self.board.data().iter().enumerate() {
-            if i % self.board.width() == 0 && i != 0 {
-                output.push_str(" |\n\r|");
+        // cells and side `|`, with the odd rows of a hexagonal board shifted
+        // right by half a cell
+        for (i, (cell, age)) in self.board.data().iter().zip(self.ages.data()).enumerate() {
+            let y = i / self.board.width();
+            if i % self.board.width() == 0 {
+                if i != 0 {
+                    output.push_str(if self.row_offset(y - 1) == 1 { "|\n\r|" } else { " |\n\r|" });
+                }
+                if self.row_offset(y) == 1 {
+                    output.push(' ');
+                }
             }
 
-            match cell {
-                Cell::Dead => output.push_str(format!(" {}", self.dead_cell).as_str()),
-                Cell::Alive => output.push_str(format!(" {}", self.alive_cell).as_str()),
+            output.push(' ');
+            let x = i % self.board.width();
+            match self.ants.iter().find(|ant| ant.x == x && ant.y == y) {
+                Some(ant) => output.push_str(&self.ant_str(ant)),
+                None => output.push_str(&self.styled_cell(*cell, *age)),
             }
         }
 
         // bottom row of `-`
-        output.push_str(" |\n\r");
+        let last = self.board.height().saturating_sub(1);
+        output.push_str(if self.row_offset(last) == 1 { "|\n\r" } else { " |\n\r" });
         for _ in 0..self.board.width() {
             output.push_str(" -");
         }
@@ -412,3 +757,28 @@ impl Display for Life {
         Ok(())
     }
 }
+
+#[test]
+fn test_resize() {
+    let mut board = Board::new([6, 4], Cell::DEAD);
+    board[[1, 1]] = Cell::ALIVE;
+    board[[5, 3]] = Cell::ALIVE;
+    let mut life = Life::new((6, 4), '.', 'O', None, Some(board));
+    life.save_state();
+    life.cursor_pos = Pos { x: 5, y: 3 };
+
+    // shrinking drops the cells that no longer fit and keeps the cursor on the board
+    life.resize((4, 3));
+    assert_eq!(life.dims(), (4, 3));
+    assert_eq!(life.board[[1, 1]], Cell::ALIVE);
+    assert_eq!(life.board.data().iter().filter(|&&cell| cell == Cell::ALIVE).count(), 1);
+    assert_eq!((life.cursor_pos.x, life.cursor_pos.y), (3, 2));
+
+    // growing adds dead cells, and a reset goes back to the resized start
+    life.resize((8, 5));
+    life.board[[7, 4]] = Cell::ALIVE;
+    life.reset();
+    assert_eq!(life.dims(), (8, 5));
+    assert_eq!(life.board[[1, 1]], Cell::ALIVE);
+    assert_eq!(life.board[[7, 4]], Cell::DEAD);
+}
@@ -0,0 +1,553 @@
+use std::fmt::Display;
+use std::path::Path;
+use std::rc::Rc;
+use std::str::FromStr;
+
+use super::colored::Colored;
+use super::elementary::Elementary;
+use super::lenia::{self, Lenia};
+use super::ltl::LargerThanLife;
+use super::margolus::Margolus;
+use super::table::{self, RuleTable};
+use super::turmite::Turmite;
+use super::Cell;
+
+/// The rule a board evolves by
+#[derive(Clone, Debug)]
+pub enum Rule {
+    LifeLike(Box<LifeLike>),
+    /// A life-like rule played by teams, whose cells are born with the color of most of their parents
+    Colored(Rc<Colored>),
+    /// A rule counting neighbors over a larger radius, stepped a whole board at a time
+    Larger(LargerThanLife),
+    /// A one dimensional rule, drawn one generation per row
+    Elementary(Elementary),
+    /// A block rule on the Margolus neighborhood, stepped a whole board at a time
+    Margolus(Margolus),
+    /// Ants walking over the board and painting its cells, stepped by `Life`
+    Turmite(Rc<Turmite>),
+    /// A continuous rule, whose cells hold values between 0 and 1
+    Lenia(Rc<Lenia>),
+    /// A rule table loaded from a Golly `.rule` file
+    Table(Rc<RuleTable>),
+}
+
+impl Default for Rule {
+    fn default() -> Self {
+        Rule::LifeLike(Box::default())
+    }
+}
+
+impl PartialEq for Rule {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Rule::LifeLike(a), Rule::LifeLike(b)) => a == b,
+            (Rule::Colored(a), Rule::Colored(b)) => a == b,
+            (Rule::Larger(a), Rule::Larger(b)) => a == b,
+            (Rule::Elementary(a), Rule::Elementary(b)) => a == b,
+            (Rule::Margolus(a), Rule::Margolus(b)) => a == b,
+            (Rule::Turmite(a), Rule::Turmite(b)) => a == b,
+            (Rule::Lenia(a), Rule::Lenia(b)) => a == b,
+            (Rule::Table(a), Rule::Table(b)) => Rc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+}
+
+impl Rule {
+    /// Number of cell states, including dead and alive
+    pub fn states(&self) -> u8 {
+        match self {
+            Rule::LifeLike(rule) => rule.states,
+            Rule::Colored(rule) => rule.teams + 1,
+            Rule::Larger(rule) => rule.states,
+            Rule::Elementary(_) | Rule::Margolus(_) => 2,
+            Rule::Turmite(turmite) => turmite.colors,
+            Rule::Lenia(_) => lenia::LEVELS,
+            Rule::Table(table) => table.states,
+        }
+    }
+
+    /// The neighbors that `next` looks at, for drawing the board to match
+    pub fn neighborhood(&self) -> Neighborhood {
+        match self {
+            Rule::LifeLike(rule) => rule.neighborhood,
+            Rule::Colored(rule) => rule.life.neighborhood,
+            _ => Neighborhood::Moore,
+        }
+    }
+
+    /// The state `cell` moves to, with `neighbors` in N, NE, E, SE, S, SW, W, NW order.
+    /// Larger than Life, one dimensional, Margolus, turmite and Lenia rules step the whole board at once instead
+    pub fn next(&self, cell: Cell, neighbors: &[Cell; 8]) -> Cell {
+        match self {
+            Rule::LifeLike(rule) => rule.next(cell, neighbor_mask(neighbors)),
+            Rule::Colored(rule) => rule.next(cell, neighbors),
+            Rule::Larger(_) => unreachable!("Larger than Life rules are stepped by `LargerThanLife::step`"),
+            Rule::Elementary(_) => unreachable!("one dimensional rules are stepped a row at a time"),
+            Rule::Margolus(_) => unreachable!("Margolus rules are stepped by `Margolus::step`"),
+            Rule::Turmite(_) => unreachable!("turmites are stepped by moving their ants"),
+            Rule::Lenia(_) => unreachable!("Lenia rules are stepped by `Lenia::step`"),
+            Rule::Table(table) => table.next(cell, neighbors),
+        }
+    }
+
+    /// Colors given by the rule itself, indexed by state
+    pub fn colors(&self) -> &[Option<crossterm::style::Color>] {
+        match self {
+            Rule::LifeLike(_) | Rule::Larger(_) | Rule::Elementary(_) | Rule::Margolus(_) | Rule::Lenia(_) => &[],
+            Rule::Table(table) => &table.colors,
+            Rule::Turmite(turmite) => &turmite.palette,
+            Rule::Colored(rule) => &rule.palette,
+        }
+    }
+
+    /// Whether the rule can be run backwards, see `Life::reverse`
+    pub fn reversible(&self) -> bool {
+        matches!(self, Rule::Margolus(rule) if rule.inverse().is_some())
+    }
+
+    /// Number of teams of a colored rule, or 0
+    pub fn teams(&self) -> u8 {
+        match self {
+            Rule::Colored(rule) => rule.teams,
+            _ => 0,
+        }
+    }
+
+    /// The rule as lines of a journal or recovery file: `rule` and its name, or
+    /// for a rule table `table` and each line of it, as only the name of a table
+    /// loaded from a file would be written otherwise
+    pub fn to_lines(&self) -> String {
+        match self {
+            Rule::Table(table) => table.source.lines().map(|line| format!("table {}\n", line)).collect(),
+            rule => format!("rule {}\n", rule),
+        }
+    }
+
+    /// A rule table from the text of the `table` lines written by `to_lines`
+    pub fn from_table(text: &str) -> Result<Rule, String> {
+        Ok(Rule::Table(Rc::new(RuleTable::parse(text)?)))
+    }
+
+    /// Reads a Golly `.rule` file
+    pub fn load(path: &Path) -> Result<Rule, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("Couldn't read rule `{}`: {}", path.display(), e))?;
+        let table = RuleTable::parse(&text).map_err(|e| format!("Invalid rule `{}`: {}", path.display(), e))?;
+        Ok(Rule::Table(Rc::new(table)))
+    }
+}
+
+impl FromStr for Rule {
+    type Err = String;
+
+    /// Accepts everything `LifeLike`, `Colored`, `LargerThanLife`, `Elementary`,
+    /// `Margolus`, `Turmite` and `Lenia` do, `wireworld`, or the path of a `.rule` or `.lenia` file
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("wireworld") {
+            return Ok(Rule::Table(Rc::new(RuleTable::parse(table::WIREWORLD)?)));
+        }
+
+        let name = s.split(':').next().unwrap().to_ascii_lowercase();
+        if name == "immigration" || name == "quadlife" {
+            return Ok(Rule::Colored(Rc::new(s.parse()?)));
+        }
+        if ["margolus", "critters", "bbm", "tron"].contains(&name.as_str()) {
+            return Ok(Rule::Margolus(s.parse()?));
+        }
+
+        if name == "lenia" {
+            return Ok(Rule::Lenia(Rc::new(s.parse()?)));
+        }
+
+        let path = Path::new(s);
+        if s.ends_with(".lenia") {
+            return Ok(Rule::Lenia(Rc::new(lenia::load(path)?.0)));
+        }
+        if s.ends_with(".rule") || path.is_file() {
+            return Rule::load(path);
+        }
+
+        let is_turn = |c: char| "LRNU".contains(c.to_ascii_uppercase());
+        if s.starts_with('{') || (s.len() >= 2 && s.chars().all(is_turn)) {
+            return Ok(Rule::Turmite(Rc::new(s.parse()?)));
+        }
+
+        let second = s.chars().nth(1);
+        if s.starts_with(['W', 'w', 'T', 't']) && second.is_some_and(|c| c.is_ascii_digit()) {
+            return Ok(Rule::Elementary(s.parse()?));
+        }
+
+        if s.starts_with(['R', 'r']) && s.contains(',') {
+            return Ok(Rule::Larger(s.parse()?));
+        }
+
+        Ok(Rule::LifeLike(Box::new(s.parse()?)))
+    }
+}
+
+impl Display for Rule {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Rule::LifeLike(rule) => rule.fmt(f),
+            Rule::Colored(rule) => rule.fmt(f),
+            Rule::Larger(rule) => rule.fmt(f),
+            Rule::Elementary(rule) => rule.fmt(f),
+            Rule::Margolus(rule) => rule.fmt(f),
+            Rule::Turmite(turmite) => turmite.fmt(f),
+            Rule::Lenia(lenia) => lenia.fmt(f),
+            Rule::Table(table) => write!(f, "{}", table.name),
+        }
+    }
+}
+
+/// The cells around a cell that count as its neighbors
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Neighborhood {
+    /// All eight surrounding cells
+    Moore,
+    /// The four orthogonal cells, written with a `V` suffix as in `B2/S013V`
+    VonNeumann,
+    /// Six cells of a hexagonal grid where every odd row is shifted half a
+    /// cell to the right, written with an `H` suffix as in `B2/S34H`
+    Hexagonal,
+}
+
+impl Neighborhood {
+    /// Whether the neighbor at `index` (N, NE, E, SE, S, SW, W, NW) of a cell in row `y` is left out
+    pub fn ignores(&self, index: usize, y: usize) -> bool {
+        match self {
+            Neighborhood::Moore => false,
+            Neighborhood::VonNeumann => index % 2 == 1,
+            // the rows above and below an even row are shifted right, so its
+            // eastern corners are too far away, and the other way around
+            Neighborhood::Hexagonal if y.is_multiple_of(2) => index == 1 || index == 3,
+            Neighborhood::Hexagonal => index == 5 || index == 7,
+        }
+    }
+
+    fn size(&self) -> usize {
+        match self {
+            Neighborhood::Moore => 8,
+            Neighborhood::VonNeumann => 4,
+            Neighborhood::Hexagonal => 6,
+        }
+    }
+}
+
+/// A life-like rule such as `B3/S23`, an isotropic non-totalistic rule such as
+/// `B2-a3/S12-k3`, or a Generations rule such as `B2/S/C3` where cells that die
+/// decay through refractory states
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LifeLike {
+    /// Indexed by the alive neighbors of a cell as a bit mask, see `neighbor_mask`
+    pub birth: [bool; 256],
+    pub survival: [bool; 256],
+    /// Number of cell states, including dead and alive. Life-like rules have 2
+    pub states: u8,
+    pub neighborhood: Neighborhood,
+}
+
+/// Letters of the isotropic arrangements of each neighbor count, in Hensel notation
+const LETTERS: [&str; 5] = ["", "ce", "ceaikn", "ceaiknjqry", "ceaiknjqrytwz"];
+
+/// An arrangement for each letter in `LETTERS`, as Golly writes them: a 3x3 grid
+/// read row by row from the top left, with the center cell as bit 4
+const ARRANGEMENTS: [&[u16]; 5] = [
+    &[0],
+    &[1, 2],
+    &[5, 10, 3, 40, 33, 68],
+    &[69, 42, 11, 7, 98, 13, 14, 70, 41, 97],
+    &[325, 170, 15, 45, 99, 71, 106, 102, 43, 101, 105, 78, 108],
+];
+
+/// The alive cells in `neighbors` (N, NE, E, SE, S, SW, W, NW) as bits 0 to 7
+pub fn neighbor_mask(neighbors: &[Cell; 8]) -> u8 {
+    neighbors
+        .iter()
+        .enumerate()
+        .filter(|(_, &n)| n == Cell::ALIVE)
+        .fold(0, |mask, (i, _)| mask | 1 << i)
+}
+
+/// Every neighbor mask that is a rotation or reflection of the arrangement for
+/// `letter` with `count` neighbors, or of every arrangement if `letter` is `None`
+fn arrangements(count: usize, letter: Option<char>) -> Vec<u8> {
+    if letter.is_none() {
+        return (0..=255u8).filter(|mask| mask.count_ones() as usize == count).collect();
+    }
+
+    // arrangements of more than four neighbors are the inverse of those of fewer
+    let (base, invert) = if count > 4 { (8 - count, true) } else { (count, false) };
+    let i = LETTERS[base].find(letter.unwrap()).unwrap();
+
+    // grid bits of N, NE, E, SE, S, SW, W and NW
+    let grid = [1, 2, 5, 8, 7, 6, 3, 0];
+    let mut mask = grid
+        .iter()
+        .enumerate()
+        .filter(|(_, &bit)| ARRANGEMENTS[base][i] & 1 << bit != 0)
+        .fold(0u8, |mask, (n, _)| mask | 1 << n);
+    if invert {
+        mask = !mask;
+    }
+
+    // a quarter turn moves each neighbor two places around the ring, and the
+    // reflection swaps east and west
+    let mut images = Vec::new();
+    for reflected in [mask, (0..8).filter(|n| mask & 1 << n != 0).fold(0u8, |m, n| m | 1 << ((8 - n) % 8))] {
+        for turns in 0..4 {
+            let image = reflected.rotate_left(turns * 2);
+            if !images.contains(&image) {
+                images.push(image);
+            }
+        }
+    }
+
+    images
+}
+
+impl Default for LifeLike {
+    fn default() -> Self {
+        "B3/S23".parse().unwrap()
+    }
+}
+
+impl LifeLike {
+    /// The state a cell in `cell` moves to with the alive neighbors in `mask`
+    pub fn next(&self, cell: Cell, mask: u8) -> Cell {
+        let mask = mask as usize;
+        match cell {
+            Cell::DEAD if self.birth[mask] => Cell::ALIVE,
+            Cell::DEAD => Cell::DEAD,
+            Cell::ALIVE if self.survival[mask] => Cell::ALIVE,
+            Cell(state) if state + 1 < self.states => Cell(state + 1),
+            _ => Cell::DEAD,
+        }
+    }
+}
+
+impl FromStr for LifeLike {
+    type Err = String;
+
+    /// Accepts `B3/S23`, `B2-a3/S12-k3`, `B2/S/C3` and the older `23/3` and
+    /// `345/2/4` (survival/birth/states) notation, followed by `V` or `H` for
+    /// the von Neumann or hexagonal neighborhoods
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (rule, neighborhood) = match s.chars().last().map(|c| c.to_ascii_uppercase()) {
+            Some('V') => (&s[..s.len() - 1], Neighborhood::VonNeumann),
+            Some('H') => (&s[..s.len() - 1], Neighborhood::Hexagonal),
+            _ => (s, Neighborhood::Moore),
+        };
+
+        let parts: Vec<&str> = rule.split('/').collect();
+        if parts.len() != 2 && parts.len() != 3 {
+            return Err(format!("Invalid rule `{}`: expected `B../S..` or `B../S../C..`", s));
+        }
+
+        let (mut birth, mut survival, mut states) = (None, None, None);
+        for (i, part) in parts.iter().enumerate() {
+            let (slot, counts) = match part.chars().next().map(|c| c.to_ascii_uppercase()) {
+                Some('B') => (&mut birth, &part[1..]),
+                Some('S') => (&mut survival, &part[1..]),
+                Some('C' | 'G') => (&mut states, &part[1..]),
+                _ => (
+                    match i {
+                        0 => &mut survival,
+                        1 => &mut birth,
+                        _ => &mut states,
+                    },
+                    *part,
+                ),
+            };
+
+            if slot.replace(counts).is_some() {
+                return Err(format!("Invalid rule `{}`: `{}` given twice", s, part));
+            }
+        }
+
+        let states = match states {
+            Some(states) => states
+                .parse()
+                .ok()
+                .filter(|&states| states >= 2)
+                .ok_or_else(|| format!("Invalid rule `{}`: the number of states must be at least 2", s))?,
+            None => 2,
+        };
+
+        Ok(LifeLike {
+            birth: parse_counts(birth.unwrap_or(""), s, neighborhood)?,
+            survival: parse_counts(survival.unwrap_or(""), s, neighborhood)?,
+            states,
+            neighborhood,
+        })
+    }
+}
+
+/// Parses counts such as `23`, optionally followed by the letters of the
+/// arrangements to include (`3ai`) or, after a `-`, to exclude (`2-a`)
+fn parse_counts(counts: &str, rule: &str, neighborhood: Neighborhood) -> Result<[bool; 256], String> {
+    let mut out = [false; 256];
+    let mut chars = counts.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        let count = match c.to_digit(10) {
+            Some(n) if n as usize <= neighborhood.size() => n as usize,
+            _ => return Err(format!("Invalid rule `{}`: unexpected `{}`", rule, c)),
+        };
+
+        let exclude = chars.next_if_eq(&'-').is_some();
+        let mut letters = Vec::new();
+        while let Some(letter) = chars.next_if(|c| c.is_ascii_alphabetic()) {
+            let letter = letter.to_ascii_lowercase();
+            if neighborhood != Neighborhood::Moore || !LETTERS[count.min(8 - count)].contains(letter) {
+                return Err(format!("Invalid rule `{}`: no arrangement `{}{}`", rule, count, letter));
+            }
+            letters.push(letter);
+        }
+        if exclude && letters.is_empty() {
+            return Err(format!("Invalid rule `{}`: expected letters after `{}-`", rule, count));
+        }
+
+        if letters.is_empty() || exclude {
+            for mask in arrangements(count, None) {
+                out[mask as usize] = true;
+            }
+        }
+        for letter in letters {
+            for mask in arrangements(count, Some(letter)) {
+                out[mask as usize] = !exclude;
+            }
+        }
+    }
+
+    Ok(out)
+}
+
+/// Writes the counts set in `masks`, with letters for the counts that are only
+/// set for some arrangements
+fn write_counts(masks: &[bool; 256]) -> String {
+    let mut out = String::new();
+
+    for count in 0..=8 {
+        let letters = LETTERS[count.min(8 - count)];
+        let set: Vec<char> = letters
+            .chars()
+            .filter(|&letter| masks[arrangements(count, Some(letter))[0] as usize])
+            .collect();
+        let any = arrangements(count, None).iter().any(|&mask| masks[mask as usize]);
+
+        if !any {
+            continue;
+        }
+
+        out.push(char::from(b'0' + count as u8));
+        if letters.is_empty() || set.len() == letters.len() {
+            continue;
+        }
+
+        if set.len() * 2 > letters.len() {
+            out.push('-');
+            out.extend(letters.chars().filter(|letter| !set.contains(letter)));
+        } else {
+            out.extend(set);
+        }
+    }
+
+    out
+}
+
+impl Display for LifeLike {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "B{}/S{}", write_counts(&self.birth), write_counts(&self.survival))?;
+        if self.states > 2 {
+            write!(f, "/C{}", self.states)?;
+        }
+        match self.neighborhood {
+            Neighborhood::Moore => {}
+            Neighborhood::VonNeumann => write!(f, "V")?,
+            Neighborhood::Hexagonal => write!(f, "H")?,
+        }
+
+        Ok(())
+    }
+}
+
+#[test]
+fn test_parse_rule() {
+    let life: LifeLike = "B3/S23".parse().unwrap();
+    assert_eq!(life, LifeLike::default());
+    assert_eq!("23/3".parse::<LifeLike>().unwrap(), life);
+    assert_eq!("s23/b3".parse::<LifeLike>().unwrap(), life);
+    assert_eq!("B36/S23".parse::<LifeLike>().unwrap().to_string(), "B36/S23");
+    assert!("B9/S23".parse::<LifeLike>().is_err());
+
+    let brians_brain: LifeLike = "B2/S/C3".parse().unwrap();
+    assert_eq!(brians_brain.states, 3);
+    assert_eq!("/2/3".parse::<LifeLike>().unwrap(), brians_brain);
+    assert_eq!(brians_brain.to_string(), "B2/S/C3");
+    assert_eq!(brians_brain.next(Cell::ALIVE, 0b111), Cell(2));
+    assert_eq!(brians_brain.next(Cell(2), 0b11), Cell::DEAD);
+    assert_eq!("345/2/4".parse::<LifeLike>().unwrap().to_string(), "B2/S345/C4");
+    assert!("B2/S/C1".parse::<LifeLike>().is_err());
+    assert!("B3S23".parse::<LifeLike>().is_err());
+
+    let wireworld: Rule = "WireWorld".parse().unwrap();
+    assert_eq!(wireworld.states(), 4);
+    assert_eq!(wireworld.to_string(), "WireWorld");
+    assert_eq!("B3/S23".parse::<Rule>().unwrap(), Rule::default());
+}
+
+#[test]
+fn test_isotropic_rules() {
+    // the letters of each count split its arrangements without overlap
+    for count in 0..=8 {
+        let mut seen = [false; 256];
+        for letter in LETTERS[count.min(8 - count)].chars() {
+            for mask in arrangements(count, Some(letter)) {
+                assert!(!seen[mask as usize]);
+                seen[mask as usize] = true;
+            }
+        }
+        if count != 0 && count != 8 {
+            assert!(arrangements(count, None).iter().all(|&mask| seen[mask as usize]));
+        }
+    }
+
+    let rule: LifeLike = "B2-a3/S12-k3".parse().unwrap();
+    assert_eq!(rule.to_string(), "B2-a3/S12-k3");
+    assert_eq!("b3/s23".parse::<LifeLike>().unwrap().to_string(), "B3/S23");
+    assert_eq!("B3/S2ce".parse::<LifeLike>().unwrap().to_string(), "B3/S2ce");
+
+    // two neighbors next to each other (2a) don't give birth, two corners (2c) do
+    assert_eq!(rule.next(Cell::DEAD, 0b11), Cell::DEAD);
+    assert_eq!(rule.next(Cell::DEAD, 0b1010), Cell::ALIVE);
+    // a knight's move apart (2k, here NW and E) doesn't survive, any three neighbors do
+    assert_eq!(rule.next(Cell::ALIVE, 0b10000100), Cell::DEAD);
+    assert_eq!(rule.next(Cell::ALIVE, 0b10001), Cell::ALIVE);
+    assert_eq!(rule.next(Cell::ALIVE, 0b100101), Cell::ALIVE);
+
+    assert!("B2x/S23".parse::<LifeLike>().is_err());
+    assert!("B0c/S23".parse::<LifeLike>().is_err());
+    assert!("B3-/S23".parse::<LifeLike>().is_err());
+}
+
+#[test]
+fn test_neighborhoods() {
+    let hex: Rule = "B2/S34H".parse().unwrap();
+    assert_eq!(hex.neighborhood(), Neighborhood::Hexagonal);
+    assert_eq!(hex.to_string(), "B2/S34H");
+    assert_eq!("b2/s013v".parse::<Rule>().unwrap().to_string(), "B2/S013V");
+    assert!("B5/S23V".parse::<Rule>().is_err());
+    assert!("B2a/S23H".parse::<Rule>().is_err());
+
+    let bosco: Rule = "R5,C0,M1,S34..58,B34..45,NM".parse().unwrap();
+    assert_eq!(bosco.to_string(), "R5,C0,M1,S34..58,B34..45,NM");
+    assert_eq!("W110".parse::<Rule>().unwrap().to_string(), "W110");
+    assert_eq!("RLR".parse::<Rule>().unwrap().states(), 3);
+
+    // an even row has its north east and south east corners left out, an odd row the western ones
+    assert!(Neighborhood::Hexagonal.ignores(1, 0) && !Neighborhood::Hexagonal.ignores(7, 0));
+    assert!(Neighborhood::Hexagonal.ignores(7, 1) && !Neighborhood::Hexagonal.ignores(1, 1));
+}
@@ -1,15 +1,34 @@
 use super::Board;
 use std::mem::size_of;
 
+/// Boards with more than two states are written after this with a byte per cell
+/// instead of being bit packed
+pub const MULTI_STATE_MAGIC: &[u8] = b"LIFEMULT";
+
 pub fn save(path: &str, board: &Board) -> Result<(), std::io::Error> {
+    std::fs::write(path, encode(board))
+}
+
+/// The board as saved, with a bit per cell unless it has more than two states
+pub fn encode(board: &Board) -> Vec<u8> {
     assert_eq!(size_of::<super::Cell>(), 1);
     let mut out = Vec::new();
+    let multi_state = board.data().iter().any(|cell| cell.0 > 1);
+
+    if multi_state {
+        out.extend_from_slice(MULTI_STATE_MAGIC);
+    }
 
     for byte in as_bytes(board.dims()) {
         out.push(*byte);
     }
 
     let data = as_bytes(board.data());
+    if multi_state {
+        out.extend_from_slice(data);
+        return out;
+    }
+
     let mut packed = 0;
     let mut bit = 0;
 
@@ -26,17 +45,17 @@ pub fn save(path: &str, board: &Board) -> Result<(), std::io::Error> {
     }
     out.push(packed);
 
-    std::fs::write(path, out)
+    out
 }
 
 fn as_bytes<T>(x: &[T]) -> &[u8] {
-    unsafe { std::slice::from_raw_parts(x.as_ptr() as *const u8, size_of::<T>() * x.len()) }
+    unsafe { std::slice::from_raw_parts(x.as_ptr() as *const u8, std::mem::size_of_val(x)) }
 }
 
 #[test]
 fn test_save() {
-    let mut board = dyn_array::DynArray::new([5, 5], super::Cell::Dead);
-    board[[0, 0]] = super::Cell::Alive;
-    board[[1, 1]] = super::Cell::Alive;
-    save("test1.dat", &board).unwrap();
+    let mut board = dyn_array::DynArray::new([5, 5], super::Cell::DEAD);
+    board[[0, 0]] = super::Cell::ALIVE;
+    board[[1, 1]] = super::Cell::ALIVE;
+    save(std::env::temp_dir().join("game_of_life_test_save.dat").to_str().unwrap(), &board).unwrap();
 }
\ No newline at end of file
@@ -0,0 +1,182 @@
+use super::lenia::Field;
+use super::{Board, Cell};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// Symmetry of a random soup, named as in apgsearch
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Symmetry {
+    None,
+    /// 180 degree rotation
+    C2,
+    /// 90 degree rotation
+    C4,
+    /// Mirrored left to right
+    D2,
+    /// Mirrored left to right and top to bottom
+    D4,
+    /// Every rotation and reflection of a square
+    D8,
+}
+
+impl FromStr for Symmetry {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_uppercase().as_str() {
+            "NONE" | "C1" => Ok(Symmetry::None),
+            "C2" => Ok(Symmetry::C2),
+            "C4" => Ok(Symmetry::C4),
+            "D2" => Ok(Symmetry::D2),
+            "D4" => Ok(Symmetry::D4),
+            "D8" => Ok(Symmetry::D8),
+            _ => Err(format!("Unknown symmetry `{}`, expected one of C1, C2, C4, D2, D4 or D8", s)),
+        }
+    }
+}
+
+impl Display for Symmetry {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Symmetry::None => write!(f, "C1"),
+            s => write!(f, "{:?}", s),
+        }
+    }
+}
+
+/// Parameters of a random fill. The same parameters always produce the same board
+#[derive(Clone, Copy, Debug)]
+pub struct Soup {
+    pub seed: u64,
+    /// Chance of each cell being alive
+    pub density: f64,
+    /// Size of the centered region to fill, or the whole board if `None`
+    pub region: Option<(usize, usize)>,
+    pub symmetry: Symmetry,
+}
+
+impl Soup {
+    /// A 50% soup over the whole board, with a random seed if none is given
+    pub fn new(seed: Option<u64>) -> Self {
+        Self {
+            seed: seed.unwrap_or_else(rand::random),
+            density: 0.5,
+            region: None,
+            symmetry: Symmetry::None,
+        }
+    }
+
+    pub fn fill(&self, board: &mut Board) {
+        let dims = [board.width(), board.height()];
+        self.fill_with(dims, |rng| rng.gen_bool(self.density), |x, y, alive| {
+            board[[x, y]] = if alive { Cell::ALIVE } else { Cell::DEAD };
+        });
+    }
+
+    /// Fills a board played by `teams` teams, with each alive cell on a random team
+    pub fn fill_teams(&self, board: &mut Board, teams: u8) {
+        let dims = [board.width(), board.height()];
+        let sample = |rng: &mut StdRng| if rng.gen_bool(self.density) { rng.gen_range(1..=teams) } else { 0 };
+        self.fill_with(dims, sample, |x, y, team| board[[x, y]] = Cell(team));
+    }
+
+    /// Fills the values of a Lenia board, with each filled cell at a random value
+    pub fn fill_field(&self, field: &mut Field) {
+        let dims = [field.width(), field.height()];
+        let sample = |rng: &mut StdRng| if rng.gen_bool(self.density) { rng.gen::<f32>() } else { 0.0 };
+        self.fill_with(dims, sample, |x, y, value| field[[x, y]] = value);
+    }
+
+    /// Calls `set` for every cell of the soup's region in a board of `dims`,
+    /// with the values drawn by `sample` shared between the cells of each orbit
+    fn fill_with<T: Copy>(
+        &self,
+        dims: [usize; 2],
+        sample: impl FnMut(&mut StdRng) -> T,
+        mut set: impl FnMut(usize, usize, T),
+    ) {
+        let (mut width, mut height) = self.region.unwrap_or((dims[0], dims[1]));
+        width = width.min(dims[0]);
+        height = height.min(dims[1]);
+
+        // rotations by 90 degrees only map a square onto itself
+        if let Symmetry::C4 | Symmetry::D8 = self.symmetry {
+            width = width.min(height);
+            height = width;
+        }
+
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let mut sample = sample;
+        let random: Vec<T> = (0..width * height).map(|_| sample(&mut rng)).collect();
+        let left = (dims[0] - width) / 2;
+        let top = (dims[1] - height) / 2;
+
+        for y in 0..height {
+            for x in 0..width {
+                let (cx, cy) = self.canonical(x, y, width, height);
+                set(left + x, top + y, random[cy * width + cx]);
+            }
+        }
+    }
+
+    /// The first of the cells that `(x, y)` is mapped to by the symmetry, so
+    /// that every cell in the same orbit reads the same random value
+    fn canonical(&self, x: usize, y: usize, width: usize, height: usize) -> (usize, usize) {
+        let (fx, fy) = (width - 1 - x, height - 1 - y);
+
+        let images: &[(usize, usize)] = match self.symmetry {
+            Symmetry::None => &[(x, y)],
+            Symmetry::C2 => &[(x, y), (fx, fy)],
+            Symmetry::C4 => &[(x, y), (fy, x), (fx, fy), (y, fx)],
+            Symmetry::D2 => &[(x, y), (fx, y)],
+            Symmetry::D4 => &[(x, y), (fx, y), (x, fy), (fx, fy)],
+            Symmetry::D8 => &[(x, y), (fy, x), (fx, fy), (y, fx), (fx, y), (x, fy), (y, x), (fy, fx)],
+        };
+
+        *images.iter().min_by_key(|&&(x, y)| (y, x)).unwrap()
+    }
+}
+
+impl Display for Soup {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "seed {}, density {}", self.seed, self.density)?;
+        if let Some((w, h)) = self.region {
+            write!(f, ", {}x{}", w, h)?;
+        }
+        if self.symmetry != Symmetry::None {
+            write!(f, ", {}", self.symmetry)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[test]
+fn test_symmetric_soup() {
+    let mut soup = Soup::new(Some(42));
+    soup.symmetry = Symmetry::D8;
+    soup.region = Some((8, 6));
+
+    let mut board = Board::new([10, 10], Cell::DEAD);
+    soup.fill(&mut board);
+
+    // the 6x6 region is centered at (2, 2)
+    for y in 0..10 {
+        for x in 0..10 {
+            let inside = (2..8).contains(&x) && (2..8).contains(&y);
+            if !inside {
+                assert_eq!(board[[x, y]], Cell::DEAD);
+                continue;
+            }
+
+            assert_eq!(board[[x, y]], board[[y, x]]);
+            assert_eq!(board[[x, y]], board[[9 - x, y]]);
+            assert_eq!(board[[x, y]], board[[x, 9 - y]]);
+        }
+    }
+
+    let mut again = Board::new([10, 10], Cell::DEAD);
+    soup.fill(&mut again);
+    assert!(board.data() == again.data());
+}
@@ -0,0 +1,314 @@
+use std::fmt::Write;
+use std::path::Path;
+use std::str::FromStr;
+
+use super::image::Render;
+use super::rule::Rule;
+use super::{Board, Cell, Life, Pos};
+
+/// Shape alive cells are drawn as
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Shape {
+    #[default]
+    Square,
+    Circle,
+}
+
+impl FromStr for Shape {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "square" => Ok(Shape::Square),
+            "circle" => Ok(Shape::Circle),
+            _ => Err(format!("Unknown cell shape `{}`, expected square or circle", s)),
+        }
+    }
+}
+
+/// A rectangle of cells, from `ul` up to but not including `lr`
+#[derive(Clone, Copy, Debug)]
+pub struct Region {
+    pub ul: Pos,
+    pub lr: Pos,
+}
+
+impl Region {
+    /// The cells of `board` inside the region, which must fit on the board
+    pub fn crop(&self, board: &Board) -> Board {
+        let mut cropped = Board::new([self.lr.x - self.ul.x, self.lr.y - self.ul.y], Cell::DEAD);
+        for ([x, y], cell) in &mut cropped {
+            *cell = board[[self.ul.x + x, self.ul.y + y]];
+        }
+        cropped
+    }
+}
+
+impl FromStr for Region {
+    type Err = String;
+
+    /// `X,Y,W,H`, with the upper left cell at `X,Y`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let numbers: Vec<usize> = s
+            .split(',')
+            .map(|n| n.trim().parse().ok())
+            .collect::<Option<_>>()
+            .filter(|n: &Vec<usize>| n.len() == 4 && n[2] > 0 && n[3] > 0)
+            .ok_or_else(|| format!("Invalid region `{}`, expected X,Y,W,H", s))?;
+
+        Ok(Region {
+            ul: Pos { x: numbers[0], y: numbers[1] },
+            lr: Pos { x: numbers[0] + numbers[2], y: numbers[1] + numbers[3] },
+        })
+    }
+}
+
+/// An arrow drawn from the middle of a spaceship the way it travels
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Arrow {
+    /// Where the arrow starts, in cells
+    pub from: (f32, f32),
+    /// Length of the arrow in cells
+    pub length: f32,
+    /// Cells the spaceship moves every period
+    pub offset: (i32, i32),
+    pub period: usize,
+}
+
+/// What is drawn on top of the cells
+#[derive(Clone, Debug, Default)]
+pub struct Overlay {
+    pub shape: Shape,
+    /// Region outlined and tinted, such as the part of a figure the text refers to
+    pub highlight: Option<Region>,
+    pub arrows: Vec<Arrow>,
+}
+
+/// Outline of a highlighted region
+const HIGHLIGHT: &str = "#ff9900";
+/// Color of spaceship arrows
+const ARROW: &str = "#d02020";
+
+/// The board as an SVG image, with every cell `render.cell_size` units across
+pub fn svg(board: &Board, render: &Render, overlay: &Overlay) -> String {
+    let size = render.cell_size;
+    let (width, height) = (board.width() * size, board.height() * size);
+    let line = (size / 8).max(1);
+    let hex = |[r, g, b]: [u8; 3]| format!("#{:02x}{:02x}{:02x}", r, g, b);
+
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#,
+        w = width,
+        h = height
+    );
+    let _ = writeln!(out, r#"<rect width="{}" height="{}" fill="{}"/>"#, width, height, hex(render.palette[0]));
+
+    // cells are grouped by state, so each color is only written once
+    let last = render.palette.len() - 1;
+    let mut states = vec![Vec::new(); last + 1];
+    for ([x, y], cell) in board {
+        if *cell != Cell::DEAD {
+            states[(cell.0 as usize).min(last)].push((x * size, y * size));
+        }
+    }
+    for (state, cells) in states.iter().enumerate().filter(|(_, cells)| !cells.is_empty()) {
+        let _ = writeln!(out, r#"<g fill="{}">"#, hex(render.palette[state]));
+        for &(x, y) in cells {
+            let _ = match overlay.shape {
+                Shape::Square => writeln!(out, r#"<rect x="{}" y="{}" width="{s}" height="{s}"/>"#, x, y, s = size),
+                Shape::Circle => {
+                    let r = size as f32 / 2.0;
+                    writeln!(out, r#"<circle cx="{}" cy="{}" r="{}"/>"#, x as f32 + r, y as f32 + r, r * 0.9)
+                }
+            };
+        }
+        let _ = writeln!(out, "</g>");
+    }
+
+    if let Some(grid) = render.grid {
+        let mut path = String::new();
+        for x in 0..=board.width() {
+            let _ = write!(path, "M{} 0V{}", x * size, height);
+        }
+        for y in 0..=board.height() {
+            let _ = write!(path, "M0 {}H{}", y * size, width);
+        }
+        let _ = writeln!(out, r#"<path d="{}" stroke="{}" stroke-width="{}" fill="none"/>"#, path, hex(grid), line);
+    }
+
+    if let Some(Region { ul, lr }) = overlay.highlight {
+        let _ = writeln!(
+            out,
+            r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{c}" fill-opacity="0.2" stroke="{c}" stroke-width="{}"/>"#,
+            ul.x * size,
+            ul.y * size,
+            lr.x.saturating_sub(ul.x) * size,
+            lr.y.saturating_sub(ul.y) * size,
+            line * 2,
+            c = HIGHLIGHT
+        );
+    }
+
+    if !overlay.arrows.is_empty() {
+        let _ = writeln!(
+            out,
+            r#"<defs><marker id="head" viewBox="0 0 10 10" refX="5" refY="5" markerWidth="4" markerHeight="4" orient="auto"><path d="M0 0L10 5L0 10z" fill="{}"/></marker></defs>"#,
+            ARROW
+        );
+    }
+    for arrow in &overlay.arrows {
+        let (dx, dy) = (arrow.offset.0 as f32, arrow.offset.1 as f32);
+        let scale = arrow.length / dx.hypot(dy);
+        let (x, y) = (arrow.from.0 * size as f32, arrow.from.1 * size as f32);
+        let _ = writeln!(
+            out,
+            r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="{}" stroke-width="{}" marker-end="url(#head)"><title>moves ({}, {}) every {} generations</title></line>"#,
+            x,
+            y,
+            x + dx * scale * size as f32,
+            y + dy * scale * size as f32,
+            ARROW,
+            line * 2,
+            arrow.offset.0,
+            arrow.offset.1,
+            arrow.period
+        );
+    }
+
+    out.push_str("</svg>\n");
+    out
+}
+
+pub fn save_svg(path: &Path, board: &Board, render: &Render, overlay: &Overlay) -> Result<(), String> {
+    std::fs::write(path, svg(board, render, overlay)).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Longest period searched for spaceships
+const MAX_PERIOD: usize = 32;
+
+/// An arrow for every spaceship on `board`: each group of cells is run on its
+/// own, and is a spaceship if it comes back moved within `MAX_PERIOD` generations
+pub fn spaceships(board: &Board, rule: &Rule) -> Vec<Arrow> {
+    // rules that don't step every cell at once have no spaceships to find this way
+    if matches!(rule, Rule::Elementary(_) | Rule::Turmite(_) | Rule::Lenia(_) | Rule::Margolus(_)) {
+        return Vec::new();
+    }
+
+    groups(board).iter().filter_map(|cells| travel(board, cells, rule)).collect()
+}
+
+/// The alive cells of `board` split into groups, with cells less than three
+/// cells apart in the same group
+fn groups(board: &Board) -> Vec<Vec<[usize; 2]>> {
+    let (width, height) = (board.width(), board.height());
+    let mut seen = Board::new([width, height], Cell::DEAD);
+    let mut groups = Vec::new();
+
+    for (start, cell) in board {
+        if *cell == Cell::DEAD || seen[start] != Cell::DEAD {
+            continue;
+        }
+
+        seen[start] = Cell::ALIVE;
+        let (mut group, mut stack) = (Vec::new(), vec![start]);
+        while let Some([x, y]) = stack.pop() {
+            group.push([x, y]);
+            for ny in y.saturating_sub(2)..(y + 3).min(height) {
+                for nx in x.saturating_sub(2)..(x + 3).min(width) {
+                    if board[[nx, ny]] != Cell::DEAD && seen[[nx, ny]] == Cell::DEAD {
+                        seen[[nx, ny]] = Cell::ALIVE;
+                        stack.push([nx, ny]);
+                    }
+                }
+            }
+        }
+        groups.push(group);
+    }
+
+    groups
+}
+
+/// The arrow for a group of cells that moves as a spaceship
+fn travel(board: &Board, cells: &[[usize; 2]], rule: &Rule) -> Option<Arrow> {
+    let ul = Pos { x: cells.iter().map(|c| c[0]).min()?, y: cells.iter().map(|c| c[1]).min()? };
+    let lr = Pos { x: cells.iter().map(|c| c[0]).max()?, y: cells.iter().map(|c| c[1]).max()? };
+    let margin = MAX_PERIOD + 2;
+
+    let mut alone = Board::new([lr.x - ul.x + 1 + margin * 2, lr.y - ul.y + 1 + margin * 2], Cell::DEAD);
+    for &[x, y] in cells {
+        alone[[x - ul.x + margin, y - ul.y + margin]] = board[[x, y]];
+    }
+    let mut life = Life::new((alone.width(), alone.height()), ' ', ' ', None, Some(alone));
+    life.rule = rule.clone();
+
+    let start = outline(&life.board)?;
+    for period in 1..=MAX_PERIOD {
+        life.tick();
+        let now = outline(&life.board)?;
+        if now.1 == start.1 && now.2 == start.2 {
+            let offset = (now.0.x as i32 - start.0.x as i32, now.0.y as i32 - start.0.y as i32);
+            if offset == (0, 0) {
+                return None;
+            }
+
+            let (width, height) = ((lr.x - ul.x + 1) as f32, (lr.y - ul.y + 1) as f32);
+            return Some(Arrow {
+                from: (ul.x as f32 + width / 2.0, ul.y as f32 + height / 2.0),
+                length: width.max(height) / 2.0 + 2.0,
+                offset,
+                period,
+            });
+        }
+    }
+
+    None
+}
+
+/// Upper left corner, size and cells of the smallest rectangle holding every
+/// alive cell, or `None` on an empty board
+fn outline(board: &Board) -> Option<(Pos, [usize; 2], Vec<Cell>)> {
+    let alive = || board.into_iter().filter(|(_, cell)| **cell != Cell::DEAD).map(|(i, _)| i);
+    let ul = Pos { x: alive().map(|i| i[0]).min()?, y: alive().map(|i| i[1]).min()? };
+    let lr = Pos { x: alive().map(|i| i[0]).max()? + 1, y: alive().map(|i| i[1]).max()? + 1 };
+
+    let cropped = Region { ul, lr }.crop(board);
+    Some((ul, [cropped.width(), cropped.height()], cropped.data().to_vec()))
+}
+
+#[test]
+fn test_svg() {
+    use super::theme::Theme;
+
+    // a glider in one corner and a blinker in the other
+    let mut board = Board::new([12, 12], Cell::DEAD);
+    for [x, y] in [[1, 0], [2, 1], [0, 2], [1, 2], [2, 2], [9, 9], [9, 10], [9, 11]] {
+        board[[x, y]] = Cell::ALIVE;
+    }
+
+    let rule = Rule::default();
+    let arrows = spaceships(&board, &rule);
+    assert_eq!(arrows.len(), 1);
+    assert_eq!((arrows[0].offset, arrows[0].period), ((1, 1), 4));
+    assert_eq!(arrows[0].from, (1.5, 1.5));
+
+    let render = Render::new(&rule, &Theme::default(), 10, Some([200, 200, 200]));
+    let overlay = Overlay {
+        shape: Shape::Circle,
+        highlight: Some("8,8,3,4".parse().unwrap()),
+        arrows,
+    };
+    let image = svg(&board, &render, &overlay);
+    assert!(image.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg" width="120" height="120""#));
+    assert_eq!(image.matches("<circle").count(), 8);
+    assert!(image.contains(r#"<circle cx="15" cy="5" r="4.5"/>"#));
+    assert!(image.contains(r#"<rect x="80" y="80" width="30" height="40""#));
+    assert!(image.contains("moves (1, 1) every 4 generations"));
+    assert!(image.ends_with("</svg>\n"));
+
+    let cropped = "8,8,3,4".parse::<Region>().unwrap().crop(&board);
+    assert_eq!(cropped.data().iter().filter(|&&c| c == Cell::ALIVE).count(), 3);
+    assert!("8,8,0,4".parse::<Region>().is_err());
+    assert_eq!("Circle".parse::<Shape>(), Ok(Shape::Circle));
+}
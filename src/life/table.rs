@@ -0,0 +1,425 @@
+use super::Cell;
+use crossterm::style::Color;
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+/// Golly's WireWorld.rule, available as `--rule wireworld`
+pub const WIREWORLD: &str = "@RULE WireWorld
+@TABLE
+n_states:4
+neighborhood:Moore
+symmetries:permute
+var a={0,1,2,3}
+var b={0,1,2,3}
+var c={0,1,2,3}
+var d={0,1,2,3}
+var e={0,1,2,3}
+var f={0,1,2,3}
+var g={0,1,2,3}
+var h={0,1,2,3}
+var i={0,2,3}
+var j={0,2,3}
+var k={0,2,3}
+var l={0,2,3}
+var m={0,2,3}
+var n={0,2,3}
+var o={0,2,3}
+# electron head -> electron tail -> copper
+1,a,b,c,d,e,f,g,h,2
+2,a,b,c,d,e,f,g,h,3
+# copper -> electron head with one or two heads around
+3,1,i,j,k,l,m,n,o,1
+3,1,1,i,j,k,l,m,n,1
+@COLORS
+0 48 48 48
+1 0 128 255
+2 255 255 255
+3 255 128 0
+";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TableNeighborhood {
+    /// N, NE, E, SE, S, SW, W, NW
+    Moore,
+    /// N, E, S, W
+    VonNeumann,
+}
+
+impl TableNeighborhood {
+    pub fn len(&self) -> usize {
+        match self {
+            TableNeighborhood::Moore => 8,
+            TableNeighborhood::VonNeumann => 4,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Entry {
+    State(u8),
+    /// Index into `RuleTable::vars`
+    Var(usize),
+}
+
+#[derive(Debug)]
+struct Transition {
+    /// Center followed by the neighbors in the order of the neighborhood
+    inputs: Vec<Entry>,
+    output: Entry,
+}
+
+/// A cellular automaton given as a Golly style `@TABLE` of transitions
+#[derive(Debug)]
+pub struct RuleTable {
+    pub name: String,
+    /// The text the table was read from, to write it out again
+    pub source: String,
+    pub states: u8,
+    pub neighborhood: TableNeighborhood,
+    /// Colors from the `@COLORS` section, indexed by state
+    pub colors: Vec<Option<Color>>,
+    vars: Vec<Vec<u8>>,
+    /// Whether each variable is bound, meaning every use in a transition takes the same value
+    bound: Vec<bool>,
+    /// Orders of the neighbors that the transitions are tried against
+    symmetries: Vec<Vec<usize>>,
+    permute: bool,
+    transitions: Vec<Transition>,
+    cache: RefCell<HashMap<[u8; 9], u8>>,
+}
+
+impl RuleTable {
+    pub fn parse(text: &str) -> Result<RuleTable, String> {
+        let mut table = RuleTable {
+            name: String::from("table"),
+            source: text.to_string(),
+            states: 0,
+            neighborhood: TableNeighborhood::Moore,
+            colors: Vec::new(),
+            vars: Vec::new(),
+            bound: Vec::new(),
+            symmetries: Vec::new(),
+            permute: false,
+            transitions: Vec::new(),
+            cache: RefCell::new(HashMap::new()),
+        };
+
+        let mut names: Vec<String> = Vec::new();
+        let mut symmetry = String::from("none");
+        let mut section = String::new();
+
+        for (i, line) in text.lines().enumerate() {
+            let err = |e: String| format!("line {}: {}", i + 1, e);
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Some(header) = line.strip_prefix('@') {
+                let mut words = header.split_whitespace();
+                section = words.next().unwrap_or("").to_string();
+                if section == "RULE" {
+                    table.name = words.next().unwrap_or("table").to_string();
+                }
+                continue;
+            }
+
+            match section.as_str() {
+                "TABLE" => {}
+                "COLORS" => {
+                    let values: Vec<u8> = line.split_whitespace().filter_map(|v| v.parse().ok()).collect();
+                    if let [state, r, g, b] = values[..] {
+                        let state = state as usize;
+                        if table.colors.len() <= state {
+                            table.colors.resize(state + 1, None);
+                        }
+                        table.colors[state] = Some(Color::Rgb { r, g, b });
+                    }
+                    continue;
+                }
+                _ => continue,
+            }
+
+            if let Some((key, value)) = line.split_once(':') {
+                let value = value.trim();
+                match key.trim() {
+                    "n_states" => {
+                        table.states = value
+                            .parse()
+                            .ok()
+                            .filter(|&n| (2..=255).contains(&n))
+                            .ok_or_else(|| err(format!("invalid n_states `{}`", value)))?
+                    }
+                    "neighborhood" => {
+                        table.neighborhood = match value {
+                            "Moore" => TableNeighborhood::Moore,
+                            "vonNeumann" => TableNeighborhood::VonNeumann,
+                            _ => return Err(err(format!("unsupported neighborhood `{}`", value))),
+                        }
+                    }
+                    "symmetries" => symmetry = value.to_string(),
+                    _ => return Err(err(format!("unknown table setting `{}`", key))),
+                }
+            } else if let Some(var) = line.strip_prefix("var ") {
+                let (name, values) = var
+                    .split_once('=')
+                    .ok_or_else(|| err(String::from("expected `var name={...}`")))?;
+                let values = table.parse_set(values.trim(), &names).map_err(err)?;
+                names.push(name.trim().to_string());
+                table.vars.push(values);
+                table.bound.push(true);
+            } else {
+                let transition = table.parse_transition(line, &names).map_err(err)?;
+                table.transitions.push(transition);
+            }
+        }
+
+        if table.states == 0 {
+            return Err(String::from("the table doesn't set n_states"));
+        }
+
+        let n = table.neighborhood.len();
+        let rotate = |step: usize| -> Vec<Vec<usize>> {
+            (0..n / step).map(|r| (0..n).map(|j| (j + r * step) % n).collect()).collect()
+        };
+        let reflect = |orders: Vec<Vec<usize>>| -> Vec<Vec<usize>> {
+            let mut all = orders.clone();
+            all.extend(orders.iter().map(|order| (0..n).map(|j| order[(n - j) % n]).collect()));
+            all
+        };
+        // rotating by a quarter turn moves the Moore neighbors two places and the von Neumann ones one
+        let quarter = n / 4;
+
+        table.symmetries = match symmetry.as_str() {
+            "none" => rotate(n),
+            "rotate4" => rotate(quarter),
+            "rotate8" if n == 8 => rotate(1),
+            "reflect_horizontal" => reflect(rotate(n)),
+            "rotate4reflect" => reflect(rotate(quarter)),
+            "rotate8reflect" if n == 8 => reflect(rotate(1)),
+            "permute" => {
+                table.permute = true;
+                rotate(n)
+            }
+            _ => return Err(format!("unsupported symmetries `{}`", symmetry)),
+        };
+
+        Ok(table)
+    }
+
+    fn parse_set(&mut self, set: &str, names: &[String]) -> Result<Vec<u8>, String> {
+        let inner = set
+            .strip_prefix('{')
+            .and_then(|s| s.strip_suffix('}'))
+            .ok_or_else(|| format!("expected a set like {{0,1}} but found `{}`", set))?;
+
+        let mut values = Vec::new();
+        for item in inner.split(',').map(str::trim) {
+            match names.iter().position(|name| name == item) {
+                Some(var) => values.extend_from_slice(&self.vars[var]),
+                None => values.push(item.parse().map_err(|_| format!("invalid state `{}`", item))?),
+            }
+        }
+
+        Ok(values)
+    }
+
+    fn parse_transition(&mut self, line: &str, names: &[String]) -> Result<Transition, String> {
+        let len = self.neighborhood.len() + 2;
+
+        // `{` sets may contain commas, so split on the commas outside of them
+        let mut items = Vec::new();
+        let mut depth = 0;
+        let mut start = 0;
+        for (i, c) in line.char_indices() {
+            match c {
+                '{' => depth += 1,
+                '}' => depth -= 1,
+                ',' if depth == 0 => {
+                    items.push(line[start..i].trim().to_string());
+                    start = i + 1;
+                }
+                _ => {}
+            }
+        }
+        items.push(line[start..].trim().to_string());
+
+        // transitions of single digit states can be written without commas
+        if items.len() == 1 && line.len() == len && line.chars().all(|c| c.is_ascii_digit()) {
+            items = line.chars().map(|c| c.to_string()).collect();
+        }
+
+        if items.len() != len {
+            return Err(format!("expected {} entries in transition but found {}", len, items.len()));
+        }
+
+        let mut entries = Vec::with_capacity(len);
+        for item in &items {
+            let entry = if let Some(var) = names.iter().position(|name| name == item) {
+                Entry::Var(var)
+            } else if item.starts_with('{') {
+                // an inline set is a variable of its own that is never bound
+                let values = self.parse_set(item, names)?;
+                self.vars.push(values);
+                self.bound.push(false);
+                Entry::Var(self.vars.len() - 1)
+            } else {
+                match item.parse::<u8>() {
+                    Ok(state) if state < self.states.max(1) || self.states == 0 => Entry::State(state),
+                    _ => return Err(format!("invalid state `{}`", item)),
+                }
+            };
+            entries.push(entry);
+        }
+
+        let output = entries.pop().unwrap();
+        if let Entry::Var(var) = output {
+            if !entries.contains(&output) || !self.bound[var] {
+                return Err(String::from("the output variable must appear in the inputs"));
+            }
+        }
+
+        Ok(Transition { inputs: entries, output })
+    }
+
+    /// The next state of `center`, with `neighbors` in Golly's N, NE, E, SE, S, SW, W, NW order
+    pub fn next(&self, center: Cell, neighbors: &[Cell; 8]) -> Cell {
+        let mut key = [center.0; 9];
+        match self.neighborhood {
+            TableNeighborhood::Moore => {
+                for (k, n) in key[1..].iter_mut().zip(neighbors) {
+                    *k = n.0;
+                }
+            }
+            TableNeighborhood::VonNeumann => {
+                for (k, n) in key[1..5].iter_mut().zip(neighbors.iter().step_by(2)) {
+                    *k = n.0;
+                }
+            }
+        }
+
+        // the order of the neighbors doesn't matter for permute, so share the cache between orders
+        if self.permute {
+            key[1..=self.neighborhood.len()].sort_unstable();
+        }
+
+        if let Some(&next) = self.cache.borrow().get(&key) {
+            return Cell(next);
+        }
+
+        let next = self.evaluate(&key[..=self.neighborhood.len()]);
+        self.cache.borrow_mut().insert(key, next);
+        Cell(next)
+    }
+
+    fn evaluate(&self, cells: &[u8]) -> u8 {
+        for transition in &self.transitions {
+            let mut bindings = vec![None; self.vars.len()];
+
+            let matched = if self.permute {
+                let mut used = vec![false; cells.len()];
+                self.matches(&transition.inputs[0], cells[0], &mut bindings)
+                    && self.match_permuted(&transition.inputs[1..], &cells[1..], &mut used, &mut bindings)
+            } else {
+                self.symmetries.iter().any(|order| {
+                    bindings.iter_mut().for_each(|b| *b = None);
+                    self.matches(&transition.inputs[0], cells[0], &mut bindings)
+                        && transition.inputs[1..]
+                            .iter()
+                            .zip(order)
+                            .all(|(entry, &i)| self.matches(entry, cells[i + 1], &mut bindings))
+                })
+            };
+
+            if matched {
+                return match transition.output {
+                    Entry::State(state) => state,
+                    Entry::Var(var) => bindings[var].unwrap(),
+                };
+            }
+        }
+
+        // cells without a matching transition stay as they are
+        cells[0]
+    }
+
+    /// Tries to give each of `entries` a different one of `cells`
+    fn match_permuted(&self, entries: &[Entry], cells: &[u8], used: &mut [bool], bindings: &mut [Option<u8>]) -> bool {
+        let (entry, rest) = match entries.split_first() {
+            Some(split) => split,
+            None => return true,
+        };
+
+        for i in 0..cells.len() {
+            if used[i] {
+                continue;
+            }
+
+            let saved = bindings.to_vec();
+            if self.matches(entry, cells[i], bindings) {
+                used[i] = true;
+                if self.match_permuted(rest, cells, used, bindings) {
+                    return true;
+                }
+                used[i] = false;
+            }
+            bindings.copy_from_slice(&saved);
+        }
+
+        false
+    }
+
+    fn matches(&self, entry: &Entry, state: u8, bindings: &mut [Option<u8>]) -> bool {
+        match *entry {
+            Entry::State(s) => s == state,
+            Entry::Var(var) => match bindings[var] {
+                Some(bound) => bound == state,
+                None if self.vars[var].contains(&state) => {
+                    if self.bound[var] {
+                        bindings[var] = Some(state);
+                    }
+                    true
+                }
+                None => false,
+            },
+        }
+    }
+}
+
+#[test]
+fn test_wireworld() {
+    let table = RuleTable::parse(WIREWORLD).unwrap();
+    assert_eq!(table.states, 4);
+    assert_eq!(table.colors[3], Some(Color::Rgb { r: 255, g: 128, b: 0 }));
+
+    let (head, tail, copper) = (Cell(1), Cell(2), Cell(3));
+    let around = |cells: &[Cell]| {
+        let mut neighbors = [Cell::DEAD; 8];
+        neighbors[..cells.len()].copy_from_slice(cells);
+        neighbors
+    };
+
+    assert_eq!(table.next(head, &around(&[copper, copper])), tail);
+    assert_eq!(table.next(tail, &around(&[head])), copper);
+    assert_eq!(table.next(copper, &around(&[head, tail, copper])), head);
+    assert_eq!(table.next(copper, &around(&[copper, head, head])), head);
+    assert_eq!(table.next(copper, &around(&[head, head, head])), copper);
+    assert_eq!(table.next(Cell::DEAD, &around(&[head, head])), Cell::DEAD);
+}
+
+#[test]
+fn test_table_symmetries() {
+    // a cell is born when its north neighbor is alive, rotated to any side
+    let text = "@RULE Spread\n@TABLE\nn_states:2\nneighborhood:vonNeumann\nsymmetries:rotate4\n010001\n";
+    let table = RuleTable::parse(text).unwrap();
+
+    let mut neighbors = [Cell::DEAD; 8];
+    neighbors[6] = Cell::ALIVE; // west
+    assert_eq!(table.next(Cell::DEAD, &neighbors), Cell::ALIVE);
+    neighbors[1] = Cell::ALIVE; // north east isn't part of the von Neumann neighborhood
+    assert_eq!(table.next(Cell::DEAD, &neighbors), Cell::ALIVE);
+    neighbors[2] = Cell::ALIVE;
+    assert_eq!(table.next(Cell::DEAD, &neighbors), Cell::DEAD);
+
+    assert!(RuleTable::parse("@TABLE\nn_states:2\nneighborhood:Moore\n0,1,0\n").is_err());
+}
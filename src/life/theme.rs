@@ -0,0 +1,149 @@
+use crossterm::style::Color;
+
+/// Cells alive for fewer generations than this are drawn as young, the rest as old
+pub const OLD_AGE: u16 = 10;
+
+/// Age colors of themes that don't have their own, such as plain
+pub const DEFAULT_AGES: [Color; 3] = [Color::White, Color::Grey, Color::DarkGrey];
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Theme {
+    pub alive: Option<Color>,
+    pub dead: Option<Color>,
+    pub background: Option<Color>,
+    /// Colors of cells that were just born, are young and are old,
+    /// `DEFAULT_AGES` if not set
+    pub ages: Option<[Color; 3]>,
+    /// Color alive cells are drawn with when `color_by_age` is set
+    pub color_by_age: bool,
+    /// Background of cells that just died, fading out over `trail` generations
+    pub trail_color: Option<Color>,
+    pub trail: usize,
+}
+
+pub const THEME_NAMES: &[&str] = &["plain", "classic", "ocean", "fire", "matrix", "mono"];
+
+impl Theme {
+    pub fn named(name: &str) -> Option<Theme> {
+        let rgb = |r, g, b| Some(Color::Rgb { r, g, b });
+
+        let (alive, dead, background, ages, trail_color) = match name {
+            "plain" => return Some(Theme::default()),
+            "classic" => (
+                rgb(0, 220, 0),
+                rgb(40, 40, 40),
+                rgb(0, 0, 0),
+                [(180, 255, 180), (0, 220, 0), (0, 110, 0)],
+                rgb(0, 70, 0),
+            ),
+            "ocean" => (
+                rgb(80, 200, 255),
+                rgb(20, 40, 70),
+                rgb(5, 15, 35),
+                [(210, 245, 255), (80, 200, 255), (30, 90, 200)],
+                rgb(20, 60, 110),
+            ),
+            "fire" => (
+                rgb(255, 140, 0),
+                rgb(60, 20, 10),
+                rgb(20, 0, 0),
+                [(255, 255, 160), (255, 140, 0), (200, 30, 0)],
+                rgb(110, 20, 0),
+            ),
+            "matrix" => (
+                rgb(0, 255, 70),
+                rgb(0, 50, 10),
+                rgb(0, 10, 0),
+                [(220, 255, 220), (0, 255, 70), (0, 140, 40)],
+                rgb(0, 80, 20),
+            ),
+            "mono" => (
+                rgb(230, 230, 230),
+                rgb(70, 70, 70),
+                rgb(0, 0, 0),
+                [(255, 255, 255), (190, 190, 190), (120, 120, 120)],
+                rgb(60, 60, 60),
+            ),
+            _ => return None,
+        };
+
+        Some(Theme {
+            alive,
+            dead,
+            background,
+            ages: Some(ages.map(|(r, g, b)| Color::Rgb { r, g, b })),
+            color_by_age: false,
+            trail_color,
+            trail: 0,
+        })
+    }
+
+    /// Foreground of an alive cell that has been alive for `age` generations
+    pub fn alive_color(&self, age: u16) -> Option<Color> {
+        if !self.color_by_age {
+            return self.alive;
+        }
+        let [newborn, young, old] = self.ages.unwrap_or(DEFAULT_AGES);
+        Some(match age {
+            0 | 1 => newborn,
+            a if a < OLD_AGE => young,
+            _ => old,
+        })
+    }
+
+    /// Foreground of a cell in refractory `state`, fading out as it decays
+    pub fn dying_color(&self, state: u8, states: u8) -> Option<Color> {
+        let alive = self.alive?;
+        let t = (state - 1) as f32 / (states - 1) as f32;
+        Some(fade(alive, self.background.unwrap_or(Color::Rgb { r: 0, g: 0, b: 0 }), t))
+    }
+
+    /// Background of a cell that died `since_death` generations ago
+    pub fn trail_background(&self, since_death: u16) -> Option<Color> {
+        let since_death = since_death as usize;
+        if since_death == 0 || since_death > self.trail {
+            return None;
+        }
+
+        let color = self.trail_color.unwrap_or(Color::DarkGrey);
+        let t = (since_death - 1) as f32 / self.trail as f32;
+        Some(fade(color, self.background.unwrap_or(Color::Rgb { r: 0, g: 0, b: 0 }), t))
+    }
+}
+
+/// Mixes `from` towards `to` by `t`. Named colors can't be mixed and are returned as is
+fn fade(from: Color, to: Color, t: f32) -> Color {
+    match (from, to) {
+        (Color::Rgb { r, g, b }, Color::Rgb { r: tr, g: tg, b: tb }) => {
+            let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+            Color::Rgb { r: mix(r, tr), g: mix(g, tg), b: mix(b, tb) }
+        }
+        _ => from,
+    }
+}
+
+#[test]
+fn test_theme_colors() {
+    let mut theme = Theme::named("fire").unwrap();
+    assert_eq!(theme.alive_color(30), theme.alive);
+
+    theme.color_by_age = true;
+    let ages = theme.ages.unwrap();
+    assert_eq!(theme.alive_color(1), Some(ages[0]));
+    assert_eq!(theme.alive_color(3), Some(ages[1]));
+    assert_eq!(theme.alive_color(OLD_AGE), Some(ages[2]));
+
+    theme.trail = 4;
+    assert_eq!(theme.trail_background(0), None);
+    assert_eq!(theme.trail_background(1), theme.trail_color);
+    assert_eq!(theme.trail_background(5), None);
+    assert!(Theme::named("nope").is_none());
+
+    // plain has no age colors of its own, so --age-colors alone uses the defaults
+    let mut plain = Theme::named("plain").unwrap();
+    assert_eq!(plain.alive_color(1), None);
+    plain.color_by_age = true;
+    assert_eq!(plain.alive_color(1), Some(DEFAULT_AGES[0]));
+    assert_eq!(plain.alive_color(3), Some(DEFAULT_AGES[1]));
+    assert_eq!(plain.alive_color(OLD_AGE), Some(DEFAULT_AGES[2]));
+}
@@ -0,0 +1,275 @@
+use crossterm::style::Color;
+use std::fmt::Display;
+use std::str::FromStr;
+
+use super::{Board, Cell, Topology};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Turn {
+    None,
+    Right,
+    /// A u-turn
+    Back,
+    Left,
+}
+
+impl Turn {
+    /// Quarter turns clockwise
+    fn quarters(&self) -> u8 {
+        match self {
+            Turn::None => 0,
+            Turn::Right => 1,
+            Turn::Back => 2,
+            Turn::Left => 3,
+        }
+    }
+}
+
+/// One ant walking over the board
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Ant {
+    pub x: usize,
+    pub y: usize,
+    /// 0 is north, then clockwise
+    pub direction: u8,
+    pub state: usize,
+}
+
+impl Ant {
+    pub fn new(x: usize, y: usize) -> Self {
+        Ant { x, y, direction: 0, state: 0 }
+    }
+
+    pub fn glyph(&self) -> char {
+        ['^', '>', 'v', '<'][self.direction as usize]
+    }
+}
+
+/// What an ant does on a cell: the color it paints it, how it turns and the
+/// state it moves to
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Transition {
+    color: u8,
+    turn: Turn,
+    state: usize,
+}
+
+/// A turmite, given either as the turns of Langton's ant and its generalizations
+/// such as `RL` and `LLRR`, or as a full table such as `{{{1,2,0},{0,8,0}}}`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Turmite {
+    /// Number of cell colors
+    pub colors: u8,
+    /// Indexed by the ant's state and then the color of its cell
+    table: Vec<Vec<Transition>>,
+    /// Written as turns rather than as a full table
+    turns_only: bool,
+    /// Colors of each cell color past alive
+    pub palette: Vec<Option<Color>>,
+}
+
+impl Turmite {
+    /// Moves `ant` one step, returning false if it walked off a bounded board
+    pub fn step(&self, ant: &mut Ant, board: &mut Board, topology: Topology) -> bool {
+        let color = board[[ant.x, ant.y]].0.min(self.colors - 1);
+        let transition = self.table[ant.state][color as usize];
+
+        board[[ant.x, ant.y]] = Cell(transition.color);
+        ant.direction = (ant.direction + transition.turn.quarters()) % 4;
+        ant.state = transition.state;
+
+        let (dx, dy) = [(0, -1), (1, 0), (0, 1), (-1, 0)][ant.direction as usize];
+        let (width, height) = (board.width() as isize, board.height() as isize);
+        let (mut x, mut y) = (ant.x as isize + dx, ant.y as isize + dy);
+        if topology == Topology::Torus {
+            x = x.rem_euclid(width);
+            y = y.rem_euclid(height);
+        } else if x < 0 || y < 0 || x >= width || y >= height {
+            return false;
+        }
+
+        ant.x = x as usize;
+        ant.y = y as usize;
+        true
+    }
+}
+
+fn with_palette(colors: u8, table: Vec<Vec<Transition>>, turns_only: bool) -> Turmite {
+    let rgb = |r, g, b| Some(Color::Rgb { r, g, b });
+    let mut palette = vec![None, None];
+    if colors > 2 {
+        // dead and alive keep the theme's colors, the rest cycle through these
+        let cycle = [rgb(255, 80, 80), rgb(80, 160, 255), rgb(255, 220, 60), rgb(200, 90, 255), rgb(60, 220, 160)];
+        palette.extend((2..colors as usize).map(|c| cycle[(c - 2) % cycle.len()]));
+    }
+
+    Turmite { colors, table, turns_only, palette }
+}
+
+impl FromStr for Turmite {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if !s.starts_with('{') {
+            let turns = s
+                .chars()
+                .map(|c| match c.to_ascii_uppercase() {
+                    'N' => Ok(Turn::None),
+                    'R' => Ok(Turn::Right),
+                    'U' => Ok(Turn::Back),
+                    'L' => Ok(Turn::Left),
+                    _ => Err(format!("Invalid ant `{}`: turns must be L, R, N or U", s)),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            if turns.len() < 2 || turns.len() > 255 {
+                return Err(format!("Invalid ant `{}`: expected 2 to 255 turns", s));
+            }
+
+            let colors = turns.len() as u8;
+            let table = vec![turns
+                .iter()
+                .enumerate()
+                .map(|(c, &turn)| Transition { color: (c as u8 + 1) % colors, turn, state: 0 })
+                .collect()];
+            return Ok(with_palette(colors, table, true));
+        }
+
+        // states, each a list of colors, each a triple of numbers
+        let err = |e: &str| format!("Invalid turmite `{}`: {}", s, e);
+        let mut table: Vec<Vec<Vec<usize>>> = Vec::new();
+        let mut depth = 0;
+        let mut number = String::new();
+        for c in s.chars().filter(|c| !c.is_whitespace()) {
+            if c.is_ascii_digit() {
+                number.push(c);
+                continue;
+            }
+            if !number.is_empty() {
+                if depth != 3 {
+                    return Err(err("numbers must be inside {color, turn, state} triples"));
+                }
+                let value = number.parse().map_err(|_| err("number out of range"))?;
+                table.last_mut().unwrap().last_mut().unwrap().push(value);
+                number.clear();
+            }
+
+            match c {
+                '{' => {
+                    depth += 1;
+                    match depth {
+                        2 => table.push(Vec::new()),
+                        3 => table.last_mut().unwrap().push(Vec::new()),
+                        1 => {}
+                        _ => return Err(err("too many `{`")),
+                    }
+                }
+                '}' if depth > 0 => depth -= 1,
+                ',' => {}
+                _ => return Err(err(&format!("unexpected `{}`", c))),
+            }
+        }
+        if depth != 0 || table.is_empty() {
+            return Err(err("unbalanced braces"));
+        }
+
+        let colors = table[0].len();
+        if !(2..=255).contains(&colors) || table.iter().any(|state| state.len() != colors) {
+            return Err(err("every state needs a triple for each of the same 2 to 255 colors"));
+        }
+
+        let mut transitions = Vec::new();
+        for state in &table {
+            let mut row = Vec::new();
+            for triple in state {
+                let [color, turn, next] = triple[..] else {
+                    return Err(err("expected {color, turn, state} triples"));
+                };
+                if color >= colors || next >= table.len() {
+                    return Err(err("color or state out of range"));
+                }
+
+                // as in Golly: 1 no turn, 2 right, 4 u-turn, 8 left
+                let turn = match turn {
+                    1 => Turn::None,
+                    2 => Turn::Right,
+                    4 => Turn::Back,
+                    8 => Turn::Left,
+                    _ => return Err(err("turns are 1 (none), 2 (right), 4 (u-turn) or 8 (left)")),
+                };
+                row.push(Transition { color: color as u8, turn, state: next });
+            }
+            transitions.push(row);
+        }
+
+        Ok(with_palette(colors as u8, transitions, false))
+    }
+}
+
+impl Display for Turmite {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let letter = |turn: Turn| match turn {
+            Turn::None => 'N',
+            Turn::Right => 'R',
+            Turn::Back => 'U',
+            Turn::Left => 'L',
+        };
+
+        if self.turns_only {
+            return write!(f, "{}", self.table[0].iter().map(|t| letter(t.turn)).collect::<String>());
+        }
+
+        let states: Vec<String> = self
+            .table
+            .iter()
+            .map(|state| {
+                let triples: Vec<String> = state
+                    .iter()
+                    .map(|t| format!("{{{},{},{}}}", t.color, 1 << t.turn.quarters(), t.state))
+                    .collect();
+                format!("{{{}}}", triples.join(","))
+            })
+            .collect();
+        write!(f, "{{{}}}", states.join(","))
+    }
+}
+
+#[test]
+fn test_langtons_ant() {
+    let ant: Turmite = "RL".parse().unwrap();
+    assert_eq!(ant.to_string(), "RL");
+
+    let mut board = Board::new([11, 11], Cell::DEAD);
+    let mut walker = Ant::new(5, 5);
+    for _ in 0..4 {
+        assert!(ant.step(&mut walker, &mut board, Topology::Bounded));
+    }
+    // four right turns on white cells walk around a square back to the start
+    assert_eq!((walker.x, walker.y, walker.direction), (5, 5, 0));
+    assert_eq!(board.data().iter().filter(|&&c| c == Cell::ALIVE).count(), 4);
+
+    // a black cell turns it left and clears the cell
+    ant.step(&mut walker, &mut board, Topology::Bounded);
+    assert_eq!(board[[5, 5]], Cell::DEAD);
+    assert_eq!(walker.glyph(), '<');
+
+    let llrr: Turmite = "llrr".parse().unwrap();
+    assert_eq!((llrr.colors, llrr.to_string().as_str()), (4, "LLRR"));
+    assert!("RX".parse::<Turmite>().is_err());
+}
+
+#[test]
+fn test_turmite_spec() {
+    // Langton's ant written out as a full table
+    let spec = "{{{1, 2, 0}, {0, 8, 0}}}";
+    let turmite: Turmite = spec.parse().unwrap();
+    assert_eq!(turmite.to_string(), "{{{1,2,0},{0,8,0}}}");
+    assert_eq!(turmite.table, "RL".parse::<Turmite>().unwrap().table);
+
+    // a spiral growth turmite with two states
+    let spiral: Turmite = "{{{1,8,1},{1,8,1}},{{1,2,1},{0,1,0}}}".parse().unwrap();
+    assert_eq!(spiral.table.len(), 2);
+
+    assert!("{{{1,2,0},{0,8,2}}}".parse::<Turmite>().is_err());
+    assert!("{{{1,3,0},{0,8,0}}}".parse::<Turmite>().is_err());
+    assert!("{{{1,2,0},{0,8,0}}".parse::<Turmite>().is_err());
+}
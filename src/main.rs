@@ -1,45 +1,105 @@
-use crossterm::{cursor, event::{self, KeyCode}, terminal, ExecutableCommand};
+use crossterm::{cursor, terminal, ExecutableCommand};
 use life::prefab;
 use life::prefab::Prefab;
+use life::chance::Chance;
+use life::colored::Census;
+use life::formats::Format;
+use life::history::{History, Snapshot};
+use life::image::{self, Render};
+use life::svg::{self, Overlay, Region};
 use life::Cell;
 use life::Life;
 use life::Pos;
+use life::rule::Rule;
+use settings::{Action, Settings};
 use std::env;
-use std::io::stdout;
 use std::io::Write;
+use journal::{Input, Journal};
+use record::out;
+use recovery::Autosave;
+use crossterm::event::KeyCode;
+use term::{Keys, Terminal};
 use std::path::{Path, PathBuf};
-use std::str::FromStr;
-use std::sync::mpsc;
-use std::thread;
+use std::rc::Rc;
 
 mod life;
 mod args;
+mod batch;
+mod journal;
+mod record;
+mod recovery;
+mod term;
+mod settings;
 
 fn main() {
-    run_life();
+    let args: Vec<String> = env::args().collect();
+
+    let result = Settings::load().and_then(|settings| {
+        args::parse(&args, &settings).map(|command| (command, settings))
+    });
+
+    let result = result.and_then(|(command, settings)| match command {
+        args::Command::Edit(config) => {
+            run_life(config, &settings, false);
+            Ok(())
+        }
+        args::Command::Run(config) => {
+            run_life(config, &settings, true);
+            Ok(())
+        }
+        args::Command::Batch(config) => batch::run(config),
+        args::Command::Convert(config) => batch::convert(config),
+        args::Command::Info(config) => batch::info(config),
+        args::Command::Bench(config) => batch::bench(config),
+        args::Command::Export(config) => batch::export(config),
+        args::Command::Replay(config) => replay(config, &settings),
+        args::Command::Help(help) => {
+            println!("{}", help);
+            Ok(())
+        }
+    });
+
+    if let Err(e) = result {
+        eprintln!("Error: {}", e);
+        std::process::exit(-1);
+    }
 }
 
-fn run_life() {
-    let args: Vec<String> = env::args().collect();
-    let config = args::Config::new(&args);
+fn run_life(config: args::Config, settings: &Settings, start_running: bool) {
     let term_size = (
         terminal::size().unwrap().0 as usize,
         terminal::size().unwrap().1 as usize,
     );
 
-    let (mut board_width, mut board_height) = if config.board_width == 0 && config.board_height == 0 {
-        (term_size.0 / 2 - (2 - term_size.0 % 2), term_size.1 - 3)
-    } else {
-        (config.board_width, config.board_height)
+    // a dimension of 0 fills the terminal in that direction
+    let filled = fill_dims(term_size.0, term_size.1);
+    let mut board_width = match config.board_width {
+        0 => filled.0,
+        w => w,
+    };
+    let mut board_height = match config.board_height {
+        0 => filled.1,
+        h => h,
     };
 
+    // a board brought back from a session that didn't end cleanly is edited first
+    let (recovered, loaded) = match offer_recovery(&settings.save_dir) {
+        Some((rule, topology, board)) => (Some((rule, topology)), Some(Ok((board, None)))),
+        None => (None, config.save_name.map(|name| {
+            get_saved_board(Path::new(&name), &settings.save_dir).map(|(board, path)| (board, Some(path)))
+        })),
+    };
+    let start_running = start_running && recovered.is_none();
+
     let mut board_save_status = None;
-    let board = match config.save_name {
-        Some(name) => {
-            match get_saved_board(Path::new(&name)) {
-                Ok(board) => {
+    let mut board_path = None;
+    let board = match loaded {
+        Some(loaded) => {
+            match loaded {
+                Ok((board, path)) => {
+                    board_path = path;
                     let check_x = (board.width() + 1) * 2 > term_size.0;
-                    let check_y = board.height() + 3 > term_size.1;
+                    let check_y = board.height() + EXTRA_ROWS > term_size.1;
                     if check_x || check_y {
                         board_save_status = Some(String::from("Terminal not large enough"));
                         None
@@ -57,9 +117,14 @@ fn run_life() {
         }
         None => None
     };
+    // a loaded board keeps its size as the terminal is resized
+    let fill = match board {
+        Some(_) => (false, false),
+        None => (config.board_width == 0, config.board_height == 0),
+    };
 
     let check_x = (board_width + 1) * 2 > term_size.0;
-    let check_y = board_height + 3 > term_size.1;
+    let check_y = board_height + EXTRA_ROWS > term_size.1;
     if check_x || check_y {
         eprintln!(
             "Error: terminal not large enough for specified dimensions. x: {}, y: {}",
@@ -72,88 +137,630 @@ fn run_life() {
         (board_width, board_height),
         config.dead_cell,
         config.alive_cell,
-        config.is_rand,
+        config.soup.filter(|_| recovered.is_none()),
         board,
     );
+    (life.rule, life.topology) = recovered.unwrap_or((config.rule, config.topology));
+    life.chance = config.chance;
+    life.dying_cell = config.dying_cell;
+    life.state_chars = config.state_chars.chars().collect();
+    life.theme = config.theme;
+    life.refill_soup();
+    life.parent = match (&board_path, life.soup) {
+        (Some(path), _) => Some(path.display().to_string()),
+        (None, Some(soup)) => Some(format!("random soup, {}", soup)),
+        (None, None) => None,
+    };
 
-    let prefabs = prefab::load_prefabs();
-
-    stdout().execute(cursor::Hide).unwrap();
-    terminal::enable_raw_mode().unwrap();
+    // a saved Lenia board brings its exact values, and its parameters unless others were asked for
+    if let Some(path) = board_path.filter(|path| path.extension().is_some_and(|ext| ext == "lenia")) {
+        if let Ok((lenia, Some(field))) = life::lenia::load(&path) {
+            if !matches!(life.rule, Rule::Lenia(_)) {
+                life.rule = Rule::Lenia(Rc::new(lenia));
+            }
+            if field.width() == life.board.width() && field.height() == life.board.height() {
+                life.set_field(field);
+            }
+        }
+    }
 
-    let (key_tx, key_rx) = mpsc::channel::<event::KeyCode>();
-    let (kill_tx, kill_rx) = mpsc::channel::<()>();
+    // the rule can come from the board or the recovery file rather than --rule
+    if let Some(Err(e)) = life.chance.as_ref().map(|chance| chance.check(&life.rule)) {
+        eprintln!("Error: {}", e);
+        std::process::exit(-1);
+    }
 
-    let input_thread = thread::spawn(move || {
-        while kill_rx.try_recv().is_err() {
-            if let Ok(true) = event::poll(std::time::Duration::from_millis(0)) {
-                if let event::Event::Key(key) =
-                    event::read().expect("An error occured while getting input")
-                {
-                    key_tx.send(key.code).unwrap();
-                }
+    if let Some(path) = &config.record {
+        if let Err(e) = record::start(Path::new(path), terminal::size().unwrap()) {
+            eprintln!("Error: {}", e);
+            std::process::exit(-1);
+        }
+    }
+    let journal = match &config.journal {
+        Some(path) => match journal::Writer::create(Path::new(path), &life, start_running, fill, config.delay, &settings.keys) {
+            Ok(journal) => Some(journal),
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(-1);
             }
+        },
+        None => None,
+    };
+
+    let (terminal, keys) = Terminal::start(config.theme.background);
+    let mut input = Input::Keys(keys, journal);
+    session(&mut life, &mut input, settings, fill, config.delay, start_running, board_save_status);
+    input.finish(&life);
+    drop(terminal);
+
+    if let Some(soup) = life.soup {
+        writeln!(out(), "Random soup: {}", soup).unwrap();
+    }
+    if let Some(chance) = &life.chance {
+        writeln!(out(), "Chances: {}", chance).unwrap();
+    }
+    if let Err(e) = record::finish() {
+        eprintln!("Error: {}", e);
+    }
+}
+
+/// Offers to bring back the board of a session that didn't end cleanly, or
+/// the board its last run started from
+fn offer_recovery(save_dir: &Path) -> Option<(Rule, life::Topology, life::Board)> {
+    let recovered = match recovery::load(save_dir)? {
+        Ok(recovered) => recovered,
+        Err(e) => {
+            eprintln!("Warning: {}", e);
+            return None;
         }
-    });
+    };
+
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs());
+    let edited = recovered.board.data() != recovered.initial.data();
+    println!(
+        "A session from {} minutes ago didn't end cleanly. {} Anything else discards it",
+        now.saturating_sub(recovered.time) / 60,
+        if edited {
+            "Restore the board being edited (e), or the board its last run started from (r)?"
+        } else {
+            "Restore its board (y)?"
+        }
+    );
 
-    let mut tick_delay = 64000;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer).ok()?;
+    let board = match answer.trim() {
+        "e" if edited => recovered.board,
+        "y" if !edited => recovered.board,
+        "r" if edited => recovered.initial,
+        _ => return None,
+    };
+    Some((recovered.rule, recovered.topology, board))
+}
+
+/// Edits and runs `life` until quit, taking every action from `input`. The
+/// board grows and shrinks with the terminal across and down as given by `fill`
+fn session(
+    life: &mut Life,
+    input: &mut Input,
+    settings: &Settings,
+    fill: (bool, bool),
+    delay: u64,
+    start_running: bool,
+    mut board_save_status: Option<String>,
+) {
+    let mut board_height = life.dims().1;
+    let prefabs = prefab::load_prefabs(&settings.prefab_dir);
+    let mut tick_delay = delay * 1000;
+    let mut skip_editor = start_running;
+    // named generations of any run of the session, to jump back to
+    let mut bookmarks: Vec<(String, usize, Snapshot)> = Vec::new();
+    // replays don't write any files
+    let mut autosave = (!input.replaying()).then(|| Autosave::new(&settings.save_dir));
 
     'outer: loop {
-        if get_initial_board(&mut life, &key_rx, board_height, &prefabs, &board_save_status) {
-            cursor_move(0, (board_height + 2) as u16);
-            break;
+        if !std::mem::take(&mut skip_editor) {
+            match get_initial_board(life, input, board_height, &prefabs, settings, &board_save_status, &mut autosave) {
+                EditorExit::Start => {}
+                EditorExit::Quit => {
+                    cursor_move(0, status_row(board_height));
+                    break;
+                }
+                EditorExit::Resize(columns, rows) => {
+                    if !resize_board(life, fill, (columns, rows))
+                        && !input.replaying()
+                        && wait_for_room(life, input, settings, fill, None)
+                    {
+                        break;
+                    }
+                    board_height = life.dims().1;
+                    continue 'outer;
+                }
+            }
         }
         life.save_state();
+        if let Some(autosave) = &mut autosave {
+            autosave.save(life);
+        }
         clear();
         board_save_status = None;
 
+        let mut run = RunState::new(life, settings.rewind);
         while !life.is_dead() {
-            life.tick();
-            purge();
-            cursor_move(0, 0);
-            print!("{}", life);
-            stdout().flush().unwrap();
-            std::thread::sleep(std::time::Duration::from_micros(tick_delay));
-
-            while let Ok(code) = key_rx.try_recv() {
-                match code {
-                    KeyCode::Char('r') => {
+            if !run.paused {
+                advance(life, &mut run);
+                print_generation(life);
+                print_status(life, &run, board_height);
+                if run.recording.as_ref().is_some_and(|frames| frames.len() >= MAX_GIF_FRAMES) {
+                    finish_recording(life, &mut run, tick_delay, settings, board_height);
+                }
+                if !input.headless() {
+                    std::thread::sleep(std::time::Duration::from_micros(tick_delay));
+                }
+            }
+
+            for action in input.run(&settings.keys, run.generation, run.paused) {
+                match action {
+                    Action::Reset => {
                         life.reset();
                         continue 'outer;
                     }
-                    KeyCode::Up => tick_delay /= 2,
-                    KeyCode::Down => tick_delay *= 2,
-                    KeyCode::Esc => break 'outer,
+                    Action::SpeedUp => tick_delay /= 2,
+                    Action::SlowDown => tick_delay *= 2,
+                    Action::Pause => {
+                        run.paused = !run.paused;
+                        print_status(life, &run, board_height);
+                    }
+                    Action::Chances if run.paused => {
+                        let prompt = "Chances, such as b3=0.9,s23=0.5,noise=0.01 (- clears a count):";
+                        let result = match get_cmd_input(input, prompt, board_height) {
+                            Some(spec) => {
+                                // kept only if the rule can take them all
+                                let mut chance = life.chance.clone().unwrap_or_else(|| Chance::new(Some(input.seed())));
+                                chance.apply(&spec).and_then(|_| chance.check(&life.rule)).map(|_| life.chance = Some(chance))
+                            }
+                            None => Ok(()),
+                        };
+
+                        clear();
+                        print_generation(life);
+                        print_status(life, &run, board_height);
+                        if let Err(e) = result {
+                            write!(out(), "  {}", e).unwrap();
+                            out().flush().unwrap();
+                        }
+                    }
+                    Action::Reverse => {
+                        run.backwards = !run.backwards && life.rule.reversible();
+                        print_status(life, &run, board_height);
+                        if !life.rule.reversible() {
+                            write!(out(), "{} isn't reversible", life.rule).unwrap();
+                            out().flush().unwrap();
+                        }
+                    }
+                    Action::Step if run.paused => {
+                        match run.history.forward(life) {
+                            Some(generation) => run.generation = generation,
+                            None => advance(life, &mut run),
+                        }
+                        print_generation(life);
+                        print_status(life, &run, board_height);
+                    }
+                    Action::StepBack if run.paused => {
+                        if let Some(generation) = run.history.back(life) {
+                            run.generation = generation;
+                        }
+                        print_generation(life);
+                        print_status(life, &run, board_height);
+                    }
+                    // edits the generation shown, which the next run starts from
+                    Action::Branch => continue 'outer,
+                    Action::Snapshot | Action::Bookmark | Action::Jump => {
+                        let prompt = match action {
+                            Action::Snapshot => String::from("Please enter a name for the snapshot to be saved as"),
+                            Action::Bookmark => String::from("Please enter a name for the bookmark"),
+                            _ => {
+                                let names: Vec<&str> = bookmarks.iter().map(|(name, ..)| name.as_str()).collect();
+                                format!("Bookmark to jump to ({}):", names.join(", "))
+                            }
+                        };
+                        let result = match (get_cmd_input(input, &prompt, board_height), action) {
+                            (None, _) => Ok(String::new()),
+                            (Some(name), Action::Snapshot) => {
+                                let name = if name.is_empty() { format!("generation-{}", run.generation) } else { name };
+                                match input.replaying() {
+                                    // replays don't write any files
+                                    true => Ok(String::new()),
+                                    false => save_snapshot(life, run.generation, &name, &settings.save_dir)
+                                        .map(|path| format!("Saved generation {} to {}", run.generation, path.display())),
+                                }
+                            }
+                            (Some(name), Action::Bookmark) => {
+                                let name = if name.is_empty() { run.generation.to_string() } else { name };
+                                bookmarks.retain(|(other, ..)| *other != name);
+                                bookmarks.push((name.clone(), run.generation, life.snapshot()));
+                                Ok(format!("Bookmarked generation {} as {}", run.generation, name))
+                            }
+                            (Some(name), _) => match bookmarks.iter().find(|(other, ..)| *other == name) {
+                                Some((_, generation, snapshot)) => {
+                                    life.restore(snapshot);
+                                    run.generation = *generation;
+                                    run.history.push(run.generation, life);
+                                    Ok(format!("Jumped to {}", name))
+                                }
+                                None => Err(format!("No bookmark named `{}`", name)),
+                            },
+                        };
+
+                        clear();
+                        print_generation(life);
+                        print_status(life, &run, board_height);
+                        write!(out(), "{}", result.unwrap_or_else(|e| e)).unwrap();
+                        out().flush().unwrap();
+                    }
+                    // replays don't write any files
+                    Action::RecordGif if !input.replaying() => match run.recording {
+                        Some(_) => finish_recording(life, &mut run, tick_delay, settings, board_height),
+                        None => {
+                            run.recording = Some(vec![life.board.clone()]);
+                            print_status(life, &run, board_height);
+                        }
+                    },
+                    Action::Screenshot if !input.replaying() => {
+                        let render = Render::new(&life.rule, &life.theme, 8, None);
+                        let result = image_path(&settings.save_dir, "board", "png")
+                            .and_then(|path| image::save_png(&path, &life.board, &render).map(|_| path));
+                        print_status(life, &run, board_height);
+                        match result {
+                            Ok(path) => write!(out(), "Saved {}", path.display()).unwrap(),
+                            Err(e) => write!(out(), "{}", e).unwrap(),
+                        }
+                        out().flush().unwrap();
+                    }
+                    Action::Resize(columns, rows) => {
+                        let dims = life.dims();
+                        // a replay draws the board whether it fits or not
+                        if !resize_board(life, fill, (columns, rows))
+                            && !input.replaying()
+                            && wait_for_room(life, input, settings, fill, Some(run.generation))
+                        {
+                            break 'outer;
+                        }
+                        board_height = life.dims().1;
+
+                        clear();
+                        print_generation(life);
+                        print_status(life, &run, board_height);
+                        // the frames of a GIF are all one size
+                        if life.dims() != dims {
+                            finish_recording(life, &mut run, tick_delay, settings, board_height);
+                        }
+                    }
+                    Action::Quit => break 'outer,
                     _ => {}
                 }
 
                 tick_delay = tick_delay.clamp(1000, 1024000);
             }
+
+            if let Some(autosave) = &mut autosave {
+                autosave.tick(life);
+            }
         }
 
         life.reset();
-        println!("\n\r\n\r\n\r----------------------------------------------\n\r All cells died!\n\r----------------------------------------------\n\r");
-        std::thread::sleep(std::time::Duration::from_millis(1500));
+        cursor_move(0, status_row(board_height));
+        out().execute(terminal::Clear(terminal::ClearType::CurrentLine)).unwrap();
+        write!(out(), "All cells died!").unwrap();
+        out().flush().unwrap();
+        if !input.headless() {
+            std::thread::sleep(std::time::Duration::from_millis(1500));
+        }
+    }
+
+    // closing the terminal isn't a clean end, so what was on it is kept to recover
+    match autosave {
+        Some(mut autosave) if term::hung_up() => autosave.save(life),
+        Some(autosave) => autosave.finish(),
+        None => {}
+    }
+}
+
+/// Plays a journal back, on the terminal or without drawing anything, and
+/// checks that it ends with the same board as when it was written
+fn replay(config: args::ReplayConfig, settings: &Settings) -> Result<(), String> {
+    let journal = Journal::load(Path::new(&config.journal), &settings.keys)?;
+    let (width, height) = (journal.board.width(), journal.board.height());
+
+    let mut life = Life::new((width, height), settings.dead_cell, settings.alive_cell, None, Some(journal.board));
+    life.rule = journal.rule;
+    life.chance = journal.chance;
+    life.topology = journal.topology;
+    life.dying_cell = settings.dying_cell;
+    life.state_chars = settings.state_chars.chars().collect();
+    life.theme = settings.theme;
+    if let Some(field) = journal.field {
+        life.set_field(field);
+    }
+
+    let events = journal.events.into();
+    let input = if config.visual {
+        let (columns, rows) = terminal::size().map_err(|e| e.to_string())?;
+        if (width + 1) * 2 > columns as usize || height + EXTRA_ROWS > rows as usize {
+            return Err(format!("The terminal is too small for the {}x{} board of the journal", width, height));
+        }
+
+        let (terminal, keys) = Terminal::start(settings.theme.background);
+        let mut input = Input::Replay(events, Some(keys));
+        session(&mut life, &mut input, settings, journal.fill, journal.delay, journal.start_running, None);
+
+        // leave the last board up until a key is pressed
+        if let Input::Replay(_, Some(keys)) | Input::Keys(keys, _) = &mut input {
+            cursor_move(0, status_row(life.dims().1));
+            out().execute(terminal::Clear(terminal::ClearType::CurrentLine)).unwrap();
+            write!(out(), "End of the replay, press any key to leave").unwrap();
+            out().flush().unwrap();
+            let _ = keys.key();
+        }
+        drop(terminal);
+        input
+    } else {
+        record::mute(true);
+        let mut input = Input::Replay(events, None);
+        session(&mut life, &mut input, settings, journal.fill, journal.delay, journal.start_running, None);
+        record::mute(false);
+        input
+    };
+    if let Some(e) = input.diverged() {
+        return Err(e);
+    }
+
+    let result = match &journal.last {
+        Some(last) if last.data() == life.board.data() => String::from("replay: ended with the same board as the journal"),
+        Some(_) => String::from("replay: ended with a different board than the journal"),
+        None => String::from("replay: the journal didn't end cleanly, so there is no board to compare with"),
+    };
+    let comments = [result.clone()];
+    match &config.output {
+        Some(path) => {
+            let path = Path::new(path);
+            let format = Format::from_path(path).unwrap_or(Format::Rle);
+            life::formats::save(path, &life.board, &life.rule, format, &comments)?;
+        }
+        None if !config.visual => print!("{}", life::formats::write_rle(&life.board, &life.rule, &comments)),
+        None => {}
+    }
+
+    match &journal.last {
+        Some(last) if last.data() != life.board.data() => Err(result),
+        _ => {
+            if config.output.is_some() || config.visual {
+                println!("{}", result);
+            }
+            Ok(())
+        }
+    }
+}
+
+fn print_generation(life: &Life) {
+    purge();
+    cursor_move(0, 0);
+    write!(out(), "{}", life).unwrap();
+    out().flush().unwrap();
+}
+
+/// Generations of team populations shown while running a colored rule
+const CENSUS_LEN: usize = 12;
+
+/// Frames a GIF recording stops at, so a forgotten recording can't use up memory
+const MAX_GIF_FRAMES: usize = 1000;
+
+/// What a run has been told to do since it left the editor
+struct RunState {
+    /// Generations run since leaving the editor
+    generation: usize,
+    paused: bool,
+    backwards: bool,
+    census: Census,
+    /// Boards recorded for a GIF since recording started
+    recording: Option<Vec<life::Board>>,
+    /// The latest generations, to step back through while paused
+    history: History,
+}
+
+impl RunState {
+    /// A run starting from `life`, keeping `rewind` generations to step back through
+    fn new(life: &Life, rewind: usize) -> Self {
+        let mut history = History::new(rewind);
+        history.push(0, life);
+        RunState {
+            generation: 0,
+            paused: false,
+            backwards: false,
+            census: Census::new(CENSUS_LEN),
+            recording: None,
+            history,
+        }
+    }
+}
+
+/// Moves `life` one generation on, or back for a reversed run
+fn advance(life: &mut Life, run: &mut RunState) {
+    if run.backwards {
+        life.reverse().expect("only reversible rules are run backwards");
+    } else {
+        life.tick();
+    }
+    run.generation += 1;
+    run.history.push(run.generation, life);
+    run.census.record(life.team_populations());
+    if let Some(frames) = &mut run.recording {
+        frames.push(life.board.clone());
+    }
+}
+
+/// Writes the recorded frames to a GIF in the save directory, each shown for
+/// as long as a tick currently takes
+fn finish_recording(life: &Life, run: &mut RunState, tick_delay: u64, settings: &Settings, board_height: usize) {
+    let Some(frames) = run.recording.take() else {
+        return;
+    };
+    let render = Render::new(&life.rule, &life.theme, 8, None);
+    let delay = (tick_delay / 10000).clamp(2, u16::MAX as u64) as u16;
+    let result = image_path(&settings.save_dir, "run", "gif")
+        .and_then(|path| image::save_gif(&path, &frames, &render, delay).map(|_| path));
+
+    print_status(life, run, board_height);
+    match result {
+        Ok(path) => write!(out(), "Saved {} frames to {}", frames.len(), path.display()).unwrap(),
+        Err(e) => write!(out(), "{}", e).unwrap(),
+    }
+    out().flush().unwrap();
+}
+
+/// Saves the generation `life` is at to `save_dir` as RLE, along with the
+/// generation number and the pattern the run came from
+fn save_snapshot(life: &Life, generation: usize, name: &str, save_dir: &Path) -> Result<PathBuf, String> {
+    std::fs::create_dir_all(save_dir).map_err(|e| format!("Failed to create {}: {}", save_dir.display(), e))?;
+    let path = save_dir.join(format!("{}.rle", name));
+    let comments = [
+        format!("generation: {}", generation),
+        format!("rule: {}", life.rule),
+        format!("parent: {}", life.parent.as_deref().unwrap_or("drawn in the editor")),
+    ];
+    life::formats::save(&path, &life.board, &life.rule, Format::Rle, &comments)?;
+    Ok(path)
+}
+
+/// A new file in `save_dir` named after the current time, such as `run-1700000000.gif`
+fn image_path(save_dir: &Path, name: &str, extension: &str) -> Result<PathBuf, String> {
+    std::fs::create_dir_all(save_dir).map_err(|e| format!("Failed to create {}: {}", save_dir.display(), e))?;
+    let secs = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs());
+    Ok(save_dir.join(format!("{}-{}.{}", name, secs, extension)))
+}
+
+/// Prints the line under the board: whether the run is paused and at which
+/// generation along with any chances, whether it runs backwards or is being
+/// recorded, and for colored rules the population of each team over the
+/// latest generations
+fn print_status(life: &Life, run: &RunState, board_height: usize) {
+    let mut line = String::new();
+    if run.paused {
+        line.push_str(&format!("Paused at generation {}", run.generation));
+        match run.history.behind() {
+            0 => line.push_str("  "),
+            behind => line.push_str(&format!(" ({} back)  ", behind)),
+        }
+    }
+    if run.backwards {
+        line.push_str("Reversed  ");
+    }
+    if let Some(frames) = &run.recording {
+        line.push_str(&format!("Recording GIF ({} frames)  ", frames.len()));
+    }
+    if let (true, Some(chance)) = (run.paused, &life.chance) {
+        line.push_str(&format!("chances: {}  ", chance));
+    }
+    for (team, population) in run.census.latest().iter().enumerate() {
+        let swatch = life.cell_str(Cell(team as u8 + 1));
+        let sparkline = run.census.sparkline(team);
+        line.push_str(&format!("{} {:<5} {}  ", swatch, population, sparkline));
     }
 
-    kill_tx.send(()).unwrap();
-    input_thread.join().unwrap();
-    terminal::disable_raw_mode().unwrap();
-    stdout().execute(cursor::Show).unwrap();
-    cursor_move(0, (board_height + 2) as u16);
+    cursor_move(0, status_row(board_height));
+    out().execute(terminal::Clear(terminal::ClearType::CurrentLine)).unwrap();
+    write!(out(), "{}", line).unwrap();
+    out().flush().unwrap();
+}
+
+/// Rows of the screen besides the board's cells: its top and bottom border,
+/// the status bar and the prompt line
+const EXTRA_ROWS: usize = 4;
+
+/// The row of the status bar, under a board `board_height` cells high
+fn status_row(board_height: usize) -> u16 {
+    (board_height + 2) as u16
+}
+
+/// The row prompts are typed on, under the status bar
+fn prompt_row(board_height: usize) -> u16 {
+    status_row(board_height) + 1
+}
+
+/// The size of a board filling a terminal of `columns` by `rows`, leaving room
+/// for its border, the status bar and the prompt line
+fn fill_dims(columns: usize, rows: usize) -> (usize, usize) {
+    ((columns / 2).saturating_sub(2 - columns % 2).max(1), rows.saturating_sub(EXTRA_ROWS).max(1))
+}
+
+/// Grows or shrinks the board in the directions it fills the terminal, to fit
+/// one of `columns` by `rows`. Returns whether the board fits
+fn resize_board(life: &mut Life, fill: (bool, bool), (columns, rows): (u16, u16)) -> bool {
+    let (columns, rows) = (columns as usize, rows as usize);
+    let filled = fill_dims(columns, rows);
+    let (width, height) = life.dims();
+    let dims = (if fill.0 { filled.0 } else { width }, if fill.1 { filled.1 } else { height });
+    if dims != life.dims() {
+        life.resize(dims);
+    }
+    (dims.0 + 1) * 2 <= columns && dims.1 + EXTRA_ROWS <= rows
+}
+
+/// Waits for the terminal to be made large enough for the board again, in the
+/// editor, or in a run at `generation`. Returns whether it was quit instead
+fn wait_for_room(life: &mut Life, input: &mut Input, settings: &Settings, fill: (bool, bool), generation: Option<usize>) -> bool {
+    let (width, height) = life.dims();
+    clear();
+    cursor_move(0, 0);
+    write!(out(), "The terminal is too small for the {}x{} board. Make it larger, or quit", width, height).unwrap();
+    out().flush().unwrap();
+
+    loop {
+        let actions = match generation {
+            Some(generation) => input.run(&settings.keys, generation, true),
+            None => input.editor(&settings.keys).into_iter().collect(),
+        };
+        for action in actions {
+            match action {
+                Action::Resize(columns, rows) if resize_board(life, fill, (columns, rows)) => return false,
+                Action::Quit => return true,
+                _ => {}
+            }
+        }
+    }
 }
 
-fn get_saved_board(path: &Path) -> Result<life::Board, String> {
+/// Loads a pattern file, or a board saved from the editor under that name. Also returns the file it was read from
+fn get_saved_board(path: &Path, save_dir: &Path) -> Result<(life::Board, PathBuf), String> {
+    if path.is_file() {
+        return Ok((life::formats::load(path)?, path.to_path_buf()));
+    }
+
+    // Lenia boards are saved with their exact values
+    let lenia_path = save_dir.join(path.to_str().unwrap().to_string() + ".lenia");
+    if lenia_path.exists() {
+        return Ok((life::formats::load(&lenia_path)?, lenia_path));
+    }
+
     let mut path_buf = PathBuf::new();
-    path_buf.push("./saves/");
+    path_buf.push(save_dir);
     path_buf.push(path.to_str().unwrap().to_string() + ".life");
 
     if !path_buf.as_path().exists() {
+        // snapshots saved during a run are RLE
+        let snapshot_path = path_buf.with_extension("rle");
+        if snapshot_path.exists() {
+            return Ok((life::formats::load(&snapshot_path)?, snapshot_path));
+        }
         return Err(format!("No such board save `{}`", path.to_str().unwrap()));
     }
 
     match life::loader::load(path_buf.as_path().to_str().unwrap()) {
-        Ok(board) => Ok(board),
+        Ok(board) => Ok((board, path_buf)),
         Err(_) => Err(String::from("Failed to process save")),
     }
 }
@@ -164,20 +771,31 @@ enum InputMode {
     SetDead,
 }
 
+/// How the editor was left
+enum EditorExit {
+    Start,
+    Quit,
+    /// The terminal now has this many columns and rows
+    Resize(u16, u16),
+}
+
 fn get_initial_board(
     life: &mut Life,
-    rx: &mpsc::Receiver<event::KeyCode>,
+    input: &mut Input,
     board_height: usize,
     prefabs: &[Prefab],
+    settings: &Settings,
     board_save_status: &Option<String>,
-) -> bool {
+    autosave: &mut Option<Autosave>,
+) -> EditorExit {
     // print setup board
     reprint_board(life);
-    if let Some((x, y)) = life.initial_cursor_pos {
-        cursor_move(x, y);
-    }
+    let (x, y) = cursor_screen_pos(life);
+    cursor_move(x, y);
 
     let mut input_mode = InputMode::Toggle;
+    // state painted in SetAlive mode and by filled rectangles
+    let mut paint = Cell::ALIVE;
     let mut status_msg = String::new();
 
     let mut status = |s| {
@@ -185,167 +803,237 @@ fn get_initial_board(
             status_msg = msg;
         }
 
-        let (prev_x, prev_y) = cursor::position().unwrap();
-        cursor_move(0, (board_height + 2) as u16);
-        stdout().execute(terminal::Clear(terminal::ClearType::CurrentLine)).unwrap();
-        print!("{}", status_msg);
-        stdout().flush().unwrap();
-        cursor_move(prev_x, prev_y);
+        out().execute(cursor::SavePosition).unwrap();
+        cursor_move(0, status_row(board_height));
+        out().execute(terminal::Clear(terminal::ClearType::CurrentLine)).unwrap();
+        write!(out(), "{}", status_msg).unwrap();
+        out().execute(cursor::RestorePosition).unwrap();
     };
 
     if let Some(msg) = board_save_status {
         status(Some(format!("Failed to load the requested board save: {}", msg)));
+    } else if let Some(soup) = life.soup {
+        status(Some(format!("Random soup: {}", soup)));
+    } else if let Rule::Elementary(rule) = &life.rule {
+        status(Some(format!("{}: draw the first generation on the top row", rule)));
     }
 
     print_cursor();
     loop {
-        if let Ok(code) = rx.recv() {
-            let char_cells = (life.dead_cell, life.alive_cell);
-
-            match code {
-                KeyCode::Up => {
-                    if life.cursor_pos.y > 0 {
-                        remove_cursor();
-                        stdout().execute(cursor::MoveUp(1)).unwrap();
-                        life.cursor_pos.y -= 1;
-                        print_cursor();
-                    }
-                }
-                KeyCode::Down => {
-                    if life.cursor_pos.y < life.dims().1 - 1 {
-                        remove_cursor();
-                        stdout().execute(cursor::MoveDown(1)).unwrap();
-                        life.cursor_pos.y += 1;
-                        print_cursor();
-                    }
-                }
-                KeyCode::Left => {
-                    if life.cursor_pos.x > 0 {
-                        remove_cursor();
-                        stdout().execute(cursor::MoveLeft(2)).unwrap();
-                        life.cursor_pos.x -= 1;
-                        print_cursor();
-                    }
-                }
-                KeyCode::Right => {
-                    if life.cursor_pos.x < life.dims().0 - 1 {
-                        remove_cursor();
-                        stdout().execute(cursor::MoveRight(2)).unwrap();
-                        life.cursor_pos.x += 1;
-                        print_cursor();
-                    }
-                }
-                KeyCode::Char(' ') => {
-                    if let InputMode::Toggle = input_mode {
-                        print_to_board(char_cells, life.toggle_cell(life.cursor_pos));
-                    }
+        let action = input.editor(&settings.keys);
+        match action {
+            Some(Action::MoveUp) if life.cursor_pos.y > 0 => {
+                remove_cursor();
+                out().execute(cursor::MoveUp(1)).unwrap();
+                life.cursor_pos.y -= 1;
+                shift_row(life, life.cursor_pos.y + 1, life.cursor_pos.y);
+                print_cursor();
+            }
+            Some(Action::MoveDown) if life.cursor_pos.y < life.dims().1 - 1 => {
+                remove_cursor();
+                out().execute(cursor::MoveDown(1)).unwrap();
+                life.cursor_pos.y += 1;
+                shift_row(life, life.cursor_pos.y - 1, life.cursor_pos.y);
+                print_cursor();
+            }
+            Some(Action::MoveLeft) if life.cursor_pos.x > 0 => {
+                remove_cursor();
+                out().execute(cursor::MoveLeft(2)).unwrap();
+                life.cursor_pos.x -= 1;
+                print_cursor();
+            }
+            Some(Action::MoveRight) if life.cursor_pos.x < life.dims().0 - 1 => {
+                remove_cursor();
+                out().execute(cursor::MoveRight(2)).unwrap();
+                life.cursor_pos.x += 1;
+                print_cursor();
+            }
+            Some(Action::Toggle) => {
+                if let InputMode::Toggle = input_mode {
+                    let cell = life.toggle_cell(life.cursor_pos);
+                    print_to_board(life, cell);
                 }
-                KeyCode::Char('s') => {
-                    if !Path::new("./saves/").exists() {
-                        std::fs::create_dir("saves").unwrap();
-                    }
-
-                    let prev_cursor_pos = cursor::position().unwrap();
-                    status(Some(String::new()));
-                    cursor_move(0, (board_height + 2) as u16);
-                    let input: String = get_cmd_input("Please enter a name for the board to be saved as").unwrap();
+            }
+            Some(Action::Save) => {
+                // cancelling saves nothing
+                if let Some(name) = get_cmd_input(input, "Please enter a name for the board to be saved as", board_height) {
                     let mut path = PathBuf::new();
-                    path.push("./saves/");
-                    path.push(input + ".life");
+                    path.push(&settings.save_dir);
+
+                    // Lenia boards keep their exact values and parameters
+                    let result = match &life.rule {
+                        // replays don't write any files
+                        _ if input.replaying() => Ok(()),
+                        Rule::Lenia(lenia) => {
+                            path.push(name + ".lenia");
+                            std::fs::create_dir_all(&settings.save_dir).map_err(|e| e.to_string())
+                                .and_then(|_| life::lenia::save(&path, lenia, Some(&life.field())))
+                        }
+                        _ => {
+                            path.push(name + ".life");
+                            std::fs::create_dir_all(&settings.save_dir)
+                                .and_then(|_| life::saver::save(path.as_path().to_str().unwrap(), &life.board))
+                                .map_err(|e| e.to_string())
+                        }
+                    };
 
-                    if let Err(e) = life::saver::save(path.as_path().to_str().unwrap(), &life.board) {
+                    if let Err(e) = result {
                         status(Some(format!("Error: failed to save board to: {}: {}", path.display(), e)));
                     }
-
-                    print_board_and_restore_cursor(life, Some(prev_cursor_pos), &mut status);
-                }
-                KeyCode::Char('1') => prefab(prefabs, 0, life, &mut status, rx),
-                KeyCode::Char('2') => prefab(prefabs, 1, life, &mut status, rx),
-                KeyCode::Char('3') => prefab(prefabs, 2, life, &mut status, rx),
-                KeyCode::Char('4') => prefab(prefabs, 3, life, &mut status, rx),
-                KeyCode::Char('5') => prefab(prefabs, 4, life, &mut status, rx),
-                KeyCode::Char('6') => prefab(prefabs, 5, life, &mut status, rx),
-                KeyCode::Char('7') => prefab(prefabs, 6, life, &mut status, rx),
-                KeyCode::Char('8') => prefab(prefabs, 7, life, &mut status, rx),
-                KeyCode::Char('9') => prefab(prefabs, 8, life, &mut status, rx),
-                KeyCode::Char('0') => prefab(prefabs, 9, life, &mut status, rx),
-                KeyCode::Char('q') => {
-                    input_mode = InputMode::Toggle;
-                    status(Some(String::from("Input mode: Toggle")));
-                }
-                KeyCode::Char('w') => {
-                    input_mode = InputMode::SetAlive;
-                    status(Some(String::from("Input mode: SetAlive")));
                 }
-                KeyCode::Char('e') => {
-                    input_mode = InputMode::SetDead;
-                    status(Some(String::from("Input mode: SetDead")));
-                }
-                KeyCode::Char('c') => fill_board_rect(life, Cell::Dead, board_height, &mut status),
-                KeyCode::Char('f') => fill_board_rect(life, Cell::Alive, board_height, &mut status),
-                KeyCode::Enter => break,
-                KeyCode::Esc => {
-                    status(Some(String::new()));
-                    return true;
+
+                print_board_and_restore_cursor(life, &mut status);
+            }
+            Some(Action::Prefab(index)) => {
+                if let Some((columns, rows)) = prefab(prefabs, index, life, &mut status, settings, input) {
+                    return EditorExit::Resize(columns, rows);
                 }
-                _ => {}
             }
+            Some(Action::ToggleMode) => {
+                input_mode = InputMode::Toggle;
+                status(Some(String::from("Input mode: Toggle")));
+            }
+            Some(Action::AliveMode) => {
+                input_mode = InputMode::SetAlive;
+                status(Some(String::from("Input mode: SetAlive")));
+            }
+            Some(Action::DeadMode) => {
+                input_mode = InputMode::SetDead;
+                status(Some(String::from("Input mode: SetDead")));
+            }
+            Some(Action::ClearRect) => fill_board_rect(life, input, Cell::DEAD, board_height, &mut status),
+            Some(Action::FillRect) => fill_board_rect(life, input, paint, board_height, &mut status),
+            Some(Action::ExportSvg) => export_svg(life, input, board_height, settings, &mut status),
+            Some(action @ (Action::NextState | Action::PrevState)) => {
+                // cycles through every state but dead
+                let alive_states = life.rule.states() - 1;
+                let step = if action == Action::NextState { 1 } else { alive_states - 1 };
+                paint = Cell((paint.0 - 1 + step) % alive_states + 1);
+                let name = if life.rule.teams() > 0 { "team" } else { "state" };
+                status(Some(format!("Painting {} {}: {}", name, paint.0, life.cell_str(paint))));
+            }
+            Some(Action::PlaceAnt) if matches!(life.rule, Rule::Turmite(_)) => {
+                life.toggle_ant(life.cursor_pos);
+                write!(out(), "{}", life.pos_str(life.cursor_pos)).unwrap();
+                out().execute(cursor::MoveLeft(1)).unwrap();
+            }
+            Some(Action::Start) => break,
+            Some(Action::Quit) => {
+                status(Some(String::new()));
+                return EditorExit::Quit;
+            }
+            Some(Action::Resize(columns, rows)) => return EditorExit::Resize(columns, rows),
+            _ => {}
+        }
 
-            match input_mode {
-                InputMode::SetAlive => {
-                    print_to_board(
-                        char_cells,
-                        life.set_cell(life.cursor_pos, life::Cell::Alive),
-                    );
-                }
-                InputMode::SetDead => {
-                    print_to_board(char_cells, life.set_cell(life.cursor_pos, life::Cell::Dead));
-                }
-                _ => {}
+        match input_mode {
+            _ if action.is_none() => {}
+            InputMode::SetAlive => {
+                let cell = life.set_cell(life.cursor_pos, paint);
+                print_to_board(life, cell);
+            }
+            InputMode::SetDead => {
+                let cell = life.set_cell(life.cursor_pos, life::Cell::DEAD);
+                print_to_board(life, cell);
             }
+            _ => {}
+        }
+
+        if let Some(autosave) = autosave {
+            autosave.tick(life);
         }
     }
 
-    life.initial_cursor_pos = Some(cursor::position().unwrap());
-    false
+    EditorExit::Start
 }
 
-fn fill_board_rect(life: &mut Life, cell: Cell, board_height: usize, status: &mut impl FnMut(Option<String>)) {
-    let get_dim = |s| {
-        let mut x = get_cmd_input(s);
-        while x.is_err() {
-            print_to_cmd("Failed to parse. Please try again.");
-            x = get_cmd_input(s);
+/// Asks for the width and height of a rectangle starting at the cursor, asking
+/// again until they are numbers. `None` if either is cancelled
+fn prompt_rect(life: &Life, input: &mut Input, board_height: usize) -> Option<(Pos, Pos)> {
+    let mut get_dim = |s: &str| {
+        let mut prompt = s.to_string();
+        loop {
+            match get_cmd_input(input, &prompt, board_height)?.parse() {
+                Ok(dim) => return Some(dim),
+                Err(_) => prompt = format!("Failed to parse, {}", s),
+            }
         }
-        x.unwrap()
     };
 
-    let prev_cursor_pos = cursor::position().unwrap();
-    status(Some(String::new()));
-    cursor_move(0, (board_height + 2) as u16);
     let lr_offset = Pos {
-        x: get_dim("width:"),
-        y: get_dim("height:"),
+        x: get_dim("width:")?,
+        y: get_dim("height:")?,
     };
 
-    if !life.fill_rect(
-        life.cursor_pos, 
-        Pos { x: life.cursor_pos.x + lr_offset.x, y: life.cursor_pos.y + lr_offset.y }, 
-        cell)
-    {
+    Some((life.cursor_pos, Pos { x: life.cursor_pos.x + lr_offset.x, y: life.cursor_pos.y + lr_offset.y }))
+}
+
+fn fill_board_rect(life: &mut Life, input: &mut Input, cell: Cell, board_height: usize, status: &mut impl FnMut(Option<String>)) {
+    if let Some((ul, lr)) = prompt_rect(life, input, board_height) {
+        if !life.fill_rect(ul, lr, cell) {
+            status(Some(String::from("Invalid selection")));
+        }
+    }
+
+    print_board_and_restore_cursor(life, status);
+}
+
+/// Writes the selected rectangle to an SVG in the save directory, with arrows
+/// over its spaceships
+fn export_svg(life: &Life, input: &mut Input, board_height: usize, settings: &Settings, status: &mut impl FnMut(Option<String>)) {
+    let Some((ul, lr)) = prompt_rect(life, input, board_height) else {
+        print_board_and_restore_cursor(life, status);
+        return;
+    };
+    let (width, height) = life.dims();
+
+    if lr.x <= ul.x || lr.y <= ul.y || lr.x > width || lr.y > height {
         status(Some(String::from("Invalid selection")));
+    } else if let Some(name) = get_cmd_input(input, "Please enter a name for the SVG", board_height) {
+        let board = Region { ul, lr }.crop(&life.board);
+        let render = Render::new(&life.rule, &life.theme, 8, None);
+        let overlay = Overlay { arrows: svg::spaceships(&board, &life.rule), ..Overlay::default() };
+        let path = settings.save_dir.join(name + ".svg");
+
+        let result = match input.replaying() {
+            // replays don't write any files
+            true => Ok(()),
+            false => std::fs::create_dir_all(&settings.save_dir)
+                .map_err(|e| e.to_string())
+                .and_then(|_| svg::save_svg(&path, &board, &render, &overlay)),
+        };
+        status(Some(match result {
+            Ok(()) => format!("Saved {}", path.display()),
+            Err(e) => format!("Error: {}", e),
+        }));
     }
 
-    print_board_and_restore_cursor(life, Some(prev_cursor_pos), status);
+    print_board_and_restore_cursor(life, status);
+}
+
+/// Where the cell under the editor's cursor is drawn
+fn cursor_screen_pos(life: &Life) -> (u16, u16) {
+    let Pos { x, y } = life.cursor_pos;
+    (2 + 2 * x as u16 + life.row_offset(y), 1 + y as u16)
 }
 
 fn reprint_board(life: &Life) {
     clear();
     cursor_move(0, 0);
-    print!("{}", life);
+    write!(out(), "{}", life).unwrap();
     cursor_move(2, 1);
 }
 
+/// Moves the cursor sideways between rows that are drawn shifted by different amounts
+fn shift_row(life: &Life, from: usize, to: usize) {
+    let (from, to) = (life.row_offset(from), life.row_offset(to));
+    if to > from {
+        out().execute(cursor::MoveRight(to - from)).unwrap();
+    } else if from > to {
+        out().execute(cursor::MoveLeft(from - to)).unwrap();
+    }
+}
+
 fn print_cursor() {
     print_around_cursor('[', ']');
 }
@@ -355,116 +1043,155 @@ fn remove_cursor() {
 }
 
 fn print_around_cursor(c1: char, c2: char) {
-    stdout().execute(cursor::MoveLeft(1)).unwrap();
-    print!("{}", c1);
-    stdout().execute(cursor::MoveRight(1)).unwrap();
-    print!("{}", c2);
-    stdout().execute(cursor::MoveLeft(2)).unwrap();
-    stdout().flush().unwrap();
+    out().execute(cursor::MoveLeft(1)).unwrap();
+    write!(out(), "{}", c1).unwrap();
+    out().execute(cursor::MoveRight(1)).unwrap();
+    write!(out(), "{}", c2).unwrap();
+    out().execute(cursor::MoveLeft(2)).unwrap();
+    out().flush().unwrap();
 }
 
-fn get_prefab_rotation(rx: &mpsc::Receiver<KeyCode>) -> Option<prefab::Rotation> {
+/// The orientation picked for a prefab, or the action that cancelled placing it
+fn get_prefab_rotation(input: &mut Input, settings: &Settings) -> Result<prefab::Rotation, Action> {
     loop {
-        if let Ok(code) = rx.recv() {
-            match code {
-                KeyCode::Up => return Some(prefab::Rotation::Up),
-                KeyCode::Down => return Some(prefab::Rotation::Down),
-                KeyCode::Left => return Some(prefab::Rotation::Left),
-                KeyCode::Right => return Some(prefab::Rotation::Right),
-                KeyCode::Char('w') => return Some(prefab::Rotation::UpFlipped),
-                KeyCode::Char('s') => return Some(prefab::Rotation::DownFlipped),
-                KeyCode::Char('a') => return Some(prefab::Rotation::LeftFlipped),
-                KeyCode::Char('d') => return Some(prefab::Rotation::RightFlipped),
-                KeyCode::Esc => return None,
-                _ => continue,
-            }
+        match input.prefab(&settings.keys) {
+            Some(Action::Rotate(rotation)) => return Ok(rotation),
+            Some(action @ (Action::Cancel | Action::Resize(..))) => return Err(action),
+            _ => continue,
         }
     }
 }
 
+/// Places a prefab at the cursor. A resize while picking its orientation
+/// cancels it, and is returned for the editor to handle
 fn prefab(
     prefabs: &[Prefab],
     index: usize,
     life: &mut Life,
     status: &mut impl FnMut(Option<String>),
-    rx: &mpsc::Receiver<event::KeyCode>
-) {
+    settings: &Settings,
+    input: &mut Input,
+) -> Option<(u16, u16)> {
     if index < prefabs.len() {
         status(Some(format!("Placing prefab {}. Select an orientation. Press esc to cancel.", prefabs[index].name)));
-        if let Err(e) = life.place_prefab(
-            &prefabs[index].board,
-            if let Some(r) = get_prefab_rotation(rx) {
-                    r
-            } else {
+        let rotation = match get_prefab_rotation(input, settings) {
+            Ok(rotation) => rotation,
+            Err(action) => {
                 status(Some(String::new()));
-                return;
-            }) {
-            match e {
-                _ => status(Some(format!("Failed to place prefab: {:?}", e)))
+                return match action {
+                    Action::Resize(columns, rows) => Some((columns, rows)),
+                    _ => None,
+                };
             }
+        };
+        if let Err(e) = life.place_prefab(&prefabs[index].board, rotation) {
+            status(Some(format!("Failed to place prefab: {:?}", e)))
         } else {
-            print_board_and_restore_cursor(life, None, status);
+            print_board_and_restore_cursor(life, status);
             status(Some(String::new()));
         }
     } else { 
         status(Some(format!("Prefab {} doesn't exist.", index + 1)))
     }
+    None
 }
 
-fn print_board_and_restore_cursor(life: &Life, prev_cursor: Option<(u16, u16)>, status: &mut impl FnMut(Option<String>)) {
-    let (x, y) = prev_cursor.unwrap_or(cursor::position().unwrap());
+fn print_board_and_restore_cursor(life: &Life, status: &mut impl FnMut(Option<String>)) {
+    let (x, y) = cursor_screen_pos(life);
     reprint_board(life);
     cursor_move(x, y);
     print_cursor();
     status(None);
 }
 
-fn print_to_board(cell_chars: (char, char), cell: Result<Cell, ()>) {
-    print!(
-        "{}",
-        match cell {
-            Ok(cell) => match cell {
-                Cell::Dead => cell_chars.0,
-                Cell::Alive => cell_chars.1,
-            },
-            Err(_) => {
-                ' '
+fn print_to_board(life: &Life, cell: Result<Cell, ()>) {
+    match cell {
+        Ok(cell) => write!(out(), "{}", life.cell_str(cell)).unwrap(),
+        Err(_) => write!(out(), " ").unwrap(),
+    }
+    out().execute(cursor::MoveLeft(1)).unwrap();
+}
+
+/// Asks for a line of text on the prompt line. `None` if it was cancelled
+fn get_cmd_input(input: &mut Input, prompt: &str, board_height: usize) -> Option<String> {
+    let row = prompt_row(board_height);
+    let text = input.text(|keys| read_line(keys, prompt, row));
+
+    // a replayed answer is shown as if it was typed
+    if let (true, Some(text)) = (input.replaying(), &text) {
+        let line: Vec<char> = text.chars().collect();
+        print_prompt(prompt, &line, line.len(), row);
+    }
+    text
+}
+
+/// Lets a line be typed after `prompt` on `row` without leaving raw mode,
+/// edited with the arrows, home, end, backspace and delete. `None` if esc is
+/// pressed or the keys stop, as they do when the session is interrupted. A
+/// resize meanwhile is handled once the line is finished
+fn read_line(keys: &mut Keys, prompt: &str, row: u16) -> Option<String> {
+    let (mut line, mut pos) = (Vec::new(), 0);
+    out().execute(cursor::Show).unwrap();
+    let text = loop {
+        print_prompt(prompt, &line, pos, row);
+        let Some(code) = keys.key() else {
+            break None;
+        };
+        match code {
+            KeyCode::Enter => break Some(line.iter().collect::<String>().trim().to_string()),
+            KeyCode::Esc => break None,
+            KeyCode::Char(c) => {
+                line.insert(pos, c);
+                pos += 1;
+            }
+            KeyCode::Backspace if pos > 0 => {
+                pos -= 1;
+                line.remove(pos);
+            }
+            KeyCode::Delete if pos < line.len() => {
+                line.remove(pos);
             }
+            KeyCode::Left => pos = pos.saturating_sub(1),
+            KeyCode::Right => pos = (pos + 1).min(line.len()),
+            KeyCode::Home => pos = 0,
+            KeyCode::End => pos = line.len(),
+            _ => {}
         }
-    );
-    stdout().execute(cursor::MoveLeft(1)).unwrap();
+    };
+    out().execute(cursor::Hide).unwrap();
+    text
 }
 
-fn get_cmd_input<T: FromStr>(prompt: &str) -> Result<T, <T as FromStr>::Err>
-{
-    terminal::disable_raw_mode().unwrap();
-    println!("{}", prompt);
-    let mut input = String::new();
-    stdout().execute(cursor::Show).unwrap();
-    std::io::stdin().read_line(&mut input).unwrap();
-    stdout().execute(cursor::Hide).unwrap();
-    terminal::enable_raw_mode().unwrap();
-    input.trim().parse()
-}
+/// Draws `prompt` and the line typed after it on `row`, with the cursor at
+/// `pos` in the line. A line too long for the terminal scrolls sideways rather
+/// than wrapping
+fn print_prompt(prompt: &str, line: &[char], pos: usize, row: u16) {
+    let columns = terminal::size().map_or(80, |(columns, _)| columns as usize);
+    let prompt: String = prompt.chars().take(columns.saturating_sub(12)).collect();
+    // the last column is kept empty, as writing to it on the bottom row can scroll the screen
+    let room = columns.saturating_sub(prompt.chars().count() + 2).max(1);
+    let start = (pos + 1).saturating_sub(room);
+    let shown: String = line[start..].iter().take(room).collect();
 
-fn print_to_cmd(s: &str) {
-    terminal::disable_raw_mode().unwrap();
-    println!("{}", s);
-    terminal::enable_raw_mode().unwrap();
+    cursor_move(0, row);
+    out().execute(terminal::Clear(terminal::ClearType::CurrentLine)).unwrap();
+    write!(out(), "{} {}", prompt, shown).unwrap();
+    cursor_move((prompt.chars().count() + 1 + pos - start) as u16, row);
+    out().flush().unwrap();
 }
 
 fn clear() {
-    stdout()
+    out()
         .execute(terminal::Clear(terminal::ClearType::All))
         .unwrap();
 }
 
 fn purge() {
-    stdout()
+    out()
         .execute(terminal::Clear(terminal::ClearType::Purge))
         .unwrap();
 }
 
 fn cursor_move(x: u16, y: u16) {
-    stdout().execute(cursor::MoveTo(x, y)).unwrap();
+    out().execute(cursor::MoveTo(x, y)).unwrap();
 }
@@ -0,0 +1,157 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Stdout, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// An asciicast v2 file being written, see https://docs.asciinema.org/manual/asciicast/v2/
+struct Recording {
+    file: BufWriter<File>,
+    start: Instant,
+    /// Output written since the last flush
+    pending: Vec<u8>,
+}
+
+static RECORDING: Mutex<Option<Recording>> = Mutex::new(None);
+
+/// Whether `out` drops everything written to it, for running the UI without a
+/// terminal, or once the terminal is gone
+static MUTED: AtomicBool = AtomicBool::new(false);
+
+pub fn mute(muted: bool) {
+    MUTED.store(muted, Ordering::Relaxed);
+}
+
+/// Starts copying everything written through `out` to an asciicast at `path`
+pub fn start(path: &Path, (width, height): (u16, u16)) -> Result<(), String> {
+    let err = |e: io::Error| format!("Failed to record to {}: {}", path.display(), e);
+    let mut file = BufWriter::new(File::create(path).map_err(err)?);
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.as_secs());
+    let term = std::env::var("TERM").unwrap_or_default();
+    writeln!(
+        file,
+        r#"{{"version": 2, "width": {}, "height": {}, "timestamp": {}, "env": {{"TERM": {}}}}}"#,
+        width,
+        height,
+        timestamp,
+        json_string(&term)
+    )
+    .map_err(err)?;
+
+    *RECORDING.lock().unwrap() = Some(Recording { file, start: Instant::now(), pending: Vec::new() });
+    Ok(())
+}
+
+/// Writes out what is left and stops recording
+pub fn finish() -> Result<(), String> {
+    match RECORDING.lock().unwrap().take() {
+        Some(mut recording) => recording.event().and_then(|_| recording.file.flush()),
+        None => Ok(()),
+    }
+    .map_err(|e| format!("Failed to finish the recording: {}", e))
+}
+
+impl Recording {
+    /// Writes the pending output as one event, keeping back the start of a
+    /// character split between writes
+    fn event(&mut self) -> io::Result<()> {
+        let valid = match std::str::from_utf8(&self.pending) {
+            Ok(s) => s.len(),
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            Err(_) => self.pending.len(),
+        };
+        if valid == 0 {
+            return Ok(());
+        }
+
+        let rest = self.pending.split_off(valid);
+        let text = String::from_utf8_lossy(&self.pending).into_owned();
+        self.pending = rest;
+
+        // outside raw mode the terminal moves to the start of the line on a newline
+        let text = text.replace("\r\n", "\n").replace('\n', "\r\n");
+        let time = self.start.elapsed().as_secs_f64();
+        writeln!(self.file, "[{:.6}, \"o\", {}]", time, json_string(&text))
+    }
+}
+
+/// `s` as a JSON string literal
+fn json_string(s: &str) -> String {
+    let mut json = String::with_capacity(s.len() + 2);
+    json.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if (c as u32) < 0x20 || c == '\u{7f}' => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+/// Standard output, or another sink, copied to the recording if there is one.
+/// Each flush becomes an event, so a frame drawn in many writes shows up at once
+pub struct Out<W: Write = Stdout>(W);
+
+pub fn out() -> Out {
+    Out(io::stdout())
+}
+
+impl<W: Write> Write for Out<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if MUTED.load(Ordering::Relaxed) {
+            return Ok(buf.len());
+        }
+
+        let written = self.0.write(buf)?;
+        if let Some(recording) = RECORDING.lock().unwrap().as_mut() {
+            recording.pending.extend_from_slice(&buf[..written]);
+        }
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if MUTED.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+
+        self.0.flush()?;
+        match RECORDING.lock().unwrap().as_mut() {
+            Some(recording) => recording.event(),
+            None => Ok(()),
+        }
+    }
+}
+
+#[test]
+fn test_recording() {
+    assert_eq!(json_string("a\"b\\\n\u{1b}[2J█"), "\"a\\\"b\\\\\\n\\u001b[2J█\"");
+
+    // written to a buffer rather than stdout, which would bypass the test's captured output
+    let mut out = Out(Vec::new());
+    let path = std::env::temp_dir().join(format!("game_of_life_test_{}.cast", std::process::id()));
+    start(&path, (80, 24)).unwrap();
+    write!(out, "one\ntwo").unwrap();
+    out.flush().unwrap();
+    // a character split across writes is kept together
+    out.write_all(&"█".as_bytes()[..1]).unwrap();
+    out.flush().unwrap();
+    out.write_all(&"█".as_bytes()[1..]).unwrap();
+    writeln!(out, "typed").unwrap();
+    finish().unwrap();
+    assert_eq!(out.0, "one\ntwo█typed\n".as_bytes());
+
+    let cast = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let lines: Vec<&str> = cast.lines().collect();
+    assert!(lines[0].starts_with(r#"{"version": 2, "width": 80, "height": 24, "timestamp": "#));
+    assert_eq!(lines.len(), 3);
+    assert!(lines[1].starts_with('[') && lines[1].ends_with(r#", "o", "one\r\ntwo"]"#));
+    assert!(lines[2].ends_with(r#", "o", "█typed\r\n"]"#));
+}
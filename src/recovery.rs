@@ -0,0 +1,194 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::life::formats;
+use crate::life::rule::Rule;
+use crate::life::{Board, Life, Topology};
+
+/// Least time between autosaves of a board that keeps changing
+const INTERVAL: Duration = Duration::from_secs(1);
+
+/// First line of every recovery file
+const HEADER: &str = "#recovery 1";
+
+/// What was autosaved of a session that didn't end cleanly
+pub struct Recovered {
+    pub rule: Rule,
+    pub topology: Topology,
+    /// The board in the editor
+    pub board: Board,
+    /// The board the last run started from
+    pub initial: Board,
+    /// When it was saved, in seconds since the epoch
+    pub time: u64,
+}
+
+/// The recovery file of sessions saving to `save_dir`
+fn path(save_dir: &Path) -> PathBuf {
+    save_dir.join("recovery.txt")
+}
+
+/// Reads the recovery file a session left in `save_dir`, if there is one
+pub fn load(save_dir: &Path) -> Option<Result<Recovered, String>> {
+    let path = path(save_dir);
+    let text = std::fs::read_to_string(&path).ok()?;
+    let err = |e: &str| format!("Invalid recovery file {}: {}", path.display(), e);
+
+    let mut lines = text.lines();
+    if lines.next() != Some(HEADER) {
+        return Some(Err(err("not a recovery file")));
+    }
+
+    let (mut rule, mut topology, mut time) = (None, Topology::Bounded, 0);
+    let mut boards = Vec::new();
+    let mut rle: Option<String> = None;
+    // a rule table is written a line at a time
+    let mut table: Option<String> = None;
+    for line in lines {
+        // the boards are RLE, ending with `!`
+        if let Some(text) = &mut rle {
+            text.push_str(line);
+            text.push('\n');
+            if line.ends_with('!') {
+                boards.push(formats::read_rle(&rle.take().unwrap()).map_err(|e| err(&e)));
+            }
+            continue;
+        }
+
+        let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+        match key {
+            "rule" => rule = Some(value.parse().map_err(|e: String| err(&e))),
+            "table" => table.get_or_insert_with(String::new).push_str(&format!("{}\n", value)),
+            "topology" => topology = value.parse().unwrap_or(Topology::Bounded),
+            "time" => time = value.parse().unwrap_or(0),
+            "board" | "initial" => rle = Some(String::new()),
+            _ => {}
+        }
+    }
+
+    if let Some(table) = table {
+        rule = Some(Rule::from_table(&table).map_err(|e| err(&e)));
+    }
+
+    let recovered = (|| {
+        let mut boards = boards.into_iter();
+        let rule = rule.ok_or_else(|| err("missing rule"))??;
+        let board = boards.next().ok_or_else(|| err("missing board"))??;
+        let initial = boards.next().ok_or_else(|| err("missing initial board"))??;
+        Ok(Recovered { rule, topology, board, initial, time })
+    })();
+    Some(recovered)
+}
+
+/// Writes the board being edited or run to the recovery file as it changes,
+/// and removes it once the session ends cleanly
+pub struct Autosave {
+    path: PathBuf,
+    last: Option<Instant>,
+    /// What was last written, without the time
+    saved: String,
+}
+
+impl Autosave {
+    pub fn new(save_dir: &Path) -> Self {
+        Autosave { path: path(save_dir), last: None, saved: String::new() }
+    }
+
+    /// Saves `life` if it changed since the last save, at most once every
+    /// `INTERVAL`. Called after every input and while waiting for one, so a
+    /// change is written within about that long
+    pub fn tick(&mut self, life: &Life) {
+        if self.last.is_some_and(|last| last.elapsed() < INTERVAL) {
+            return;
+        }
+        self.last = Some(Instant::now());
+        let body = body(life);
+        if body != self.saved {
+            self.write(body);
+        }
+    }
+
+    /// Saves the board and the state the last run started from straight away
+    pub fn save(&mut self, life: &Life) {
+        self.last = Some(Instant::now());
+        self.write(body(life));
+    }
+
+    /// A failed autosave is given up on rather than stopping the session
+    fn write(&mut self, body: String) {
+        let time = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.as_secs());
+        let text = format!("{}\ntime {}\n{}", HEADER, time, body);
+        self.saved = body;
+
+        // written next to it first, so a crash while saving leaves the last one whole
+        let temp = self.path.with_extension("tmp");
+        let _ = self
+            .path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|_| std::fs::File::create(&temp))
+            .and_then(|mut file| file.write_all(text.as_bytes()).and_then(|_| file.sync_all()))
+            .and_then(|_| std::fs::rename(&temp, &self.path));
+    }
+
+    /// The session ended cleanly, so there is nothing to recover
+    pub fn finish(self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// What is saved of `life`, but for the header and time
+fn body(life: &Life) -> String {
+    format!(
+        "{}topology {}\nboard\n{}initial\n{}",
+        life.rule.to_lines(),
+        life.topology,
+        formats::write_rle(&life.board, &life.rule, &[]),
+        formats::write_rle(life.initial_state(), &life.rule, &[]),
+    )
+}
+
+#[test]
+fn test_recovery() {
+    use crate::life::Cell;
+
+    let dir = std::env::temp_dir().join(format!("game_of_life_test_recovery_{}", std::process::id()));
+    let mut board = Board::new([6, 4], Cell::DEAD);
+    board[[1, 2]] = Cell::ALIVE;
+    let mut life = Life::new((6, 4), ' ', '#', None, Some(board));
+    life.save_state();
+    life.board[[4, 0]] = Cell::ALIVE;
+    life.topology = Topology::Torus;
+
+    assert!(load(&dir).is_none());
+    let mut autosave = Autosave::new(&dir);
+    autosave.save(&life);
+    let recovered = load(&dir).unwrap().unwrap();
+    assert_eq!(recovered.rule.to_string(), "B3/S23");
+    assert_eq!(recovered.topology, Topology::Torus);
+    assert!(recovered.board.data() == life.board.data());
+    assert!(recovered.initial.data() == life.initial_state().data());
+    assert!(recovered.time > 0);
+
+    // a rule table read from a file is kept whole rather than by its name
+    life.rule = Rule::from_table("@RULE Spread\n@TABLE\nn_states:2\nneighborhood:vonNeumann\nsymmetries:rotate4\n010001\n").unwrap();
+    autosave.save(&life);
+    let recovered = load(&dir).unwrap().unwrap();
+    assert_eq!(recovered.rule.to_string(), "Spread");
+    assert_eq!(recovered.rule.to_lines(), life.rule.to_lines());
+
+    // a tick writes a change straight away, and waits a moment before the next
+    let mut ticked = Autosave::new(&dir);
+    life.board[[0, 3]] = Cell::ALIVE;
+    ticked.tick(&life);
+    assert!(load(&dir).unwrap().unwrap().board.data() == life.board.data());
+    life.board[[0, 3]] = Cell::DEAD;
+    ticked.tick(&life);
+    assert!(load(&dir).unwrap().unwrap().board.data() != life.board.data());
+
+    // nothing is left once the session ends
+    autosave.finish();
+    assert!(load(&dir).is_none());
+    std::fs::remove_dir(&dir).unwrap();
+}
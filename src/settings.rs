@@ -0,0 +1,418 @@
+use crossterm::event::KeyCode;
+use crossterm::style::Color;
+use std::path::{Path, PathBuf};
+
+use crate::life::rule::Rule;
+use crate::life::prefab::Rotation;
+use crate::life::theme::{Theme, DEFAULT_AGES, THEME_NAMES};
+use crate::life::Topology;
+
+/// Defaults read from `game_of_life.toml`. Command line flags take priority over these
+pub struct Settings {
+    pub board_width: usize,
+    pub board_height: usize,
+    pub dead_cell: char,
+    pub alive_cell: char,
+    pub dying_cell: char,
+    pub state_chars: String,
+    pub rule: Rule,
+    pub topology: Topology,
+    pub theme: Theme,
+    /// Delay between ticks in milliseconds
+    pub delay: u64,
+    /// Generations kept to step back through during a run
+    pub rewind: usize,
+    pub save_dir: PathBuf,
+    pub prefab_dir: PathBuf,
+    pub keys: KeyBindings,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            board_width: 0,
+            board_height: 0,
+            dead_cell: ' ',
+            alive_cell: '#',
+            dying_cell: '+',
+            state_chars: String::new(),
+            rule: Rule::default(),
+            topology: Topology::Bounded,
+            theme: Theme::default(),
+            delay: 64,
+            rewind: 256,
+            save_dir: PathBuf::from("./saves/"),
+            prefab_dir: PathBuf::from("./prefabs/"),
+            keys: KeyBindings::default(),
+        }
+    }
+}
+
+impl Settings {
+    /// Applies `$XDG_CONFIG_HOME/game_of_life/config.toml` (or `~/.config/...`)
+    /// followed by `./game_of_life.toml`, so the local file wins
+    pub fn load() -> Result<Self, String> {
+        let mut settings = Settings::default();
+
+        let config_home = std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")));
+
+        let mut paths = Vec::new();
+        if let Some(config_home) = config_home {
+            paths.push(config_home.join("game_of_life").join("config.toml"));
+        }
+        paths.push(PathBuf::from("./game_of_life.toml"));
+
+        for path in paths {
+            if !path.is_file() {
+                continue;
+            }
+
+            let text = std::fs::read_to_string(&path)
+                .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+            settings
+                .apply(&text)
+                .map_err(|e| format!("{}: {}", path.display(), e))?;
+        }
+
+        Ok(settings)
+    }
+
+    fn apply(&mut self, text: &str) -> Result<(), String> {
+        let mut section = String::new();
+
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                section = name.trim().to_string();
+                continue;
+            }
+
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| format!("line {}: expected `key = value`", i + 1))?;
+            let value = parse_value(value.trim()).map_err(|e| format!("line {}: {}", i + 1, e))?;
+
+            self.set(&section, key.trim(), &value)
+                .map_err(|e| format!("line {}: {}", i + 1, e))?;
+        }
+
+        Ok(())
+    }
+
+    fn set(&mut self, section: &str, key: &str, value: &str) -> Result<(), String> {
+        let number = |value: &str| {
+            value
+                .parse()
+                .map_err(|_| format!("expected a number for `{}`", key))
+        };
+        let character = |value: &str| {
+            let mut chars = value.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Ok(c),
+                _ => Err(format!("expected a single character for `{}`", key)),
+            }
+        };
+
+        match (section, key) {
+            ("", "width") => self.board_width = number(value)?,
+            ("", "height") => self.board_height = number(value)?,
+            ("", "delay") => self.delay = number(value)? as u64,
+            ("", "rewind") => self.rewind = number(value)?,
+            ("", "dead_char") => self.dead_cell = character(value)?,
+            ("", "alive_char") => self.alive_cell = character(value)?,
+            ("", "dying_char") => self.dying_cell = character(value)?,
+            ("", "state_chars") => self.state_chars = value.to_string(),
+            ("", "rule") => self.rule = value.parse()?,
+            ("", "topology") => self.topology = value.parse()?,
+            ("", "save_dir") => self.save_dir = PathBuf::from(value),
+            ("", "prefab_dir") => self.prefab_dir = PathBuf::from(value),
+            ("colors", "theme") => self.theme = named_theme(value)?,
+            ("colors", "alive") => self.theme.alive = Some(parse_color(value)?),
+            ("colors", "dead") => self.theme.dead = Some(parse_color(value)?),
+            ("colors", "background") => self.theme.background = Some(parse_color(value)?),
+            ("colors", "age") => {
+                self.theme.color_by_age = value
+                    .parse()
+                    .map_err(|_| String::from("expected true or false for `age`"))?
+            }
+            ("colors", "newborn" | "young" | "old") => {
+                let index = ["newborn", "young", "old"].iter().position(|&k| k == key).unwrap();
+                let mut ages = self.theme.ages.unwrap_or(DEFAULT_AGES);
+                ages[index] = parse_color(value)?;
+                self.theme.ages = Some(ages);
+            }
+            ("colors", "trail") => self.theme.trail = number(value)?,
+            ("colors", "trail_color") => self.theme.trail_color = Some(parse_color(value)?),
+            ("keys.editor", _) => self.keys.editor.bind(key, parse_key(value)?)?,
+            ("keys.prefab", _) => self.keys.prefab.bind(key, parse_key(value)?)?,
+            ("keys.run", _) => self.keys.run.bind(key, parse_key(value)?)?,
+            _ => return Err(format!("unknown setting `{}` in [{}]", key, section)),
+        }
+
+        Ok(())
+    }
+}
+
+/// Strips the quotes from a string value. Numbers and booleans are kept as written
+fn parse_value(value: &str) -> Result<String, String> {
+    if let Some(rest) = value.strip_prefix('"') {
+        let end = rest.find('"').ok_or_else(|| String::from("unterminated string"))?;
+        let trailing = rest[end + 1..].trim();
+        if !trailing.is_empty() && !trailing.starts_with('#') {
+            return Err(format!("unexpected `{}` after string", trailing));
+        }
+
+        Ok(rest[..end].to_string())
+    } else {
+        Ok(value.split('#').next().unwrap().trim().to_string())
+    }
+}
+
+pub fn named_theme(name: &str) -> Result<Theme, String> {
+    Theme::named(name).ok_or_else(|| format!("unknown theme `{}`, expected one of {}", name, THEME_NAMES.join(", ")))
+}
+
+/// Accepts crossterm's color names (`dark_grey`, `red`, ...) and `#rrggbb`
+pub fn parse_color(s: &str) -> Result<Color, String> {
+    if let Some(hex) = s.strip_prefix('#') {
+        let channel = |i: usize| {
+            hex.get(i..i + 2)
+                .and_then(|c| u8::from_str_radix(c, 16).ok())
+                .ok_or_else(|| format!("invalid color `{}`", s))
+        };
+
+        if hex.len() != 6 {
+            return Err(format!("invalid color `{}`", s));
+        }
+        return Ok(Color::Rgb { r: channel(0)?, g: channel(2)?, b: channel(4)? });
+    }
+
+    Color::try_from(s).map_err(|_| format!("unknown color `{}`", s))
+}
+
+pub fn parse_key(s: &str) -> Result<KeyCode, String> {
+    let mut chars = s.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Ok(KeyCode::Char(c));
+    }
+
+    Ok(match s.to_lowercase().as_str() {
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "enter" | "return" => KeyCode::Enter,
+        "esc" | "escape" => KeyCode::Esc,
+        "space" => KeyCode::Char(' '),
+        "tab" => KeyCode::Tab,
+        "backspace" => KeyCode::Backspace,
+        "delete" => KeyCode::Delete,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        f if f.starts_with('f') && f[1..].parse::<u8>().is_ok() => KeyCode::F(f[1..].parse().unwrap()),
+        _ => return Err(format!("unknown key `{}`", s)),
+    })
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    Toggle,
+    Save,
+    ToggleMode,
+    AliveMode,
+    DeadMode,
+    ClearRect,
+    FillRect,
+    /// Choose the state painted by alive mode and filled rectangles
+    NextState,
+    PrevState,
+    Start,
+    Quit,
+    /// Place the prefab at this index
+    Prefab(usize),
+    Rotate(Rotation),
+    Cancel,
+    Reset,
+    SpeedUp,
+    SlowDown,
+    /// Stop or resume the run; while stopped, `Step` advances one generation
+    Pause,
+    /// Also steps forward again through generations stepped back from
+    Step,
+    /// Step back through the latest generations while paused
+    StepBack,
+    /// Go to the editor with the generation shown
+    Branch,
+    /// Save the generation shown along with where it came from
+    Snapshot,
+    /// Name the generation shown, to jump back to later in the session
+    Bookmark,
+    Jump,
+    /// Change the birth and survival chances and the noise, while paused
+    Chances,
+    /// Run a reversible rule backwards, or forwards again
+    Reverse,
+    /// Start recording the run, or stop and save the recording as a GIF
+    RecordGif,
+    /// Save the board as a PNG
+    Screenshot,
+    /// Place or remove a turmite's ant under the cursor
+    PlaceAnt,
+    /// Save a rectangle of the board as an SVG
+    ExportSvg,
+    /// The terminal now has this many columns and rows. Not bound to a key
+    Resize(u16, u16),
+}
+
+/// Maps keys to actions for one part of the UI
+#[derive(Clone)]
+pub struct Bindings {
+    /// Setting name, action and default key of every bindable action
+    defaults: &'static [(&'static str, Action, KeyCode)],
+    keys: Vec<(KeyCode, Action)>,
+}
+
+impl Bindings {
+    fn new(defaults: &'static [(&'static str, Action, KeyCode)]) -> Self {
+        Self {
+            defaults,
+            keys: defaults.iter().map(|&(_, action, key)| (key, action)).collect(),
+        }
+    }
+
+    pub fn action(&self, key: KeyCode) -> Option<Action> {
+        self.keys.iter().find(|(k, _)| *k == key).map(|&(_, action)| action)
+    }
+
+    /// The setting name of `action`
+    pub fn name(&self, action: Action) -> Option<&'static str> {
+        self.defaults.iter().find(|(_, a, _)| *a == action).map(|&(name, _, _)| name)
+    }
+
+    /// The action with the setting name `name`
+    pub fn named(&self, name: &str) -> Option<Action> {
+        self.defaults.iter().find(|(n, _, _)| *n == name).map(|&(_, action, _)| action)
+    }
+
+    fn bind(&mut self, name: &str, key: KeyCode) -> Result<(), String> {
+        let action = self.named(name).ok_or_else(|| format!("unknown action `{}`", name))?;
+
+        self.keys.retain(|&(k, a)| a != action && k != key);
+        self.keys.push((key, action));
+        Ok(())
+    }
+}
+
+#[derive(Clone)]
+pub struct KeyBindings {
+    /// Keys used while editing the initial board
+    pub editor: Bindings,
+    /// Keys used while choosing the orientation of a prefab
+    pub prefab: Bindings,
+    /// Keys used while the simulation is running
+    pub run: Bindings,
+}
+
+const EDITOR_DEFAULTS: &[(&str, Action, KeyCode)] = &[
+    ("up", Action::MoveUp, KeyCode::Up),
+    ("down", Action::MoveDown, KeyCode::Down),
+    ("left", Action::MoveLeft, KeyCode::Left),
+    ("right", Action::MoveRight, KeyCode::Right),
+    ("toggle", Action::Toggle, KeyCode::Char(' ')),
+    ("save", Action::Save, KeyCode::Char('s')),
+    ("toggle_mode", Action::ToggleMode, KeyCode::Char('q')),
+    ("alive_mode", Action::AliveMode, KeyCode::Char('w')),
+    ("dead_mode", Action::DeadMode, KeyCode::Char('e')),
+    ("clear_rect", Action::ClearRect, KeyCode::Char('c')),
+    ("fill_rect", Action::FillRect, KeyCode::Char('f')),
+    ("next_state", Action::NextState, KeyCode::Char(']')),
+    ("prev_state", Action::PrevState, KeyCode::Char('[')),
+    ("ant", Action::PlaceAnt, KeyCode::Char('a')),
+    ("svg", Action::ExportSvg, KeyCode::Char('v')),
+    ("start", Action::Start, KeyCode::Enter),
+    ("quit", Action::Quit, KeyCode::Esc),
+    ("prefab_1", Action::Prefab(0), KeyCode::Char('1')),
+    ("prefab_2", Action::Prefab(1), KeyCode::Char('2')),
+    ("prefab_3", Action::Prefab(2), KeyCode::Char('3')),
+    ("prefab_4", Action::Prefab(3), KeyCode::Char('4')),
+    ("prefab_5", Action::Prefab(4), KeyCode::Char('5')),
+    ("prefab_6", Action::Prefab(5), KeyCode::Char('6')),
+    ("prefab_7", Action::Prefab(6), KeyCode::Char('7')),
+    ("prefab_8", Action::Prefab(7), KeyCode::Char('8')),
+    ("prefab_9", Action::Prefab(8), KeyCode::Char('9')),
+    ("prefab_10", Action::Prefab(9), KeyCode::Char('0')),
+];
+
+const PREFAB_DEFAULTS: &[(&str, Action, KeyCode)] = &[
+    ("up", Action::Rotate(Rotation::Up), KeyCode::Up),
+    ("down", Action::Rotate(Rotation::Down), KeyCode::Down),
+    ("left", Action::Rotate(Rotation::Left), KeyCode::Left),
+    ("right", Action::Rotate(Rotation::Right), KeyCode::Right),
+    ("up_flipped", Action::Rotate(Rotation::UpFlipped), KeyCode::Char('w')),
+    ("down_flipped", Action::Rotate(Rotation::DownFlipped), KeyCode::Char('s')),
+    ("left_flipped", Action::Rotate(Rotation::LeftFlipped), KeyCode::Char('a')),
+    ("right_flipped", Action::Rotate(Rotation::RightFlipped), KeyCode::Char('d')),
+    ("cancel", Action::Cancel, KeyCode::Esc),
+];
+
+const RUN_DEFAULTS: &[(&str, Action, KeyCode)] = &[
+    ("reset", Action::Reset, KeyCode::Char('r')),
+    ("speed_up", Action::SpeedUp, KeyCode::Up),
+    ("slow_down", Action::SlowDown, KeyCode::Down),
+    ("pause", Action::Pause, KeyCode::Char('p')),
+    ("step", Action::Step, KeyCode::Char('n')),
+    ("step_back", Action::StepBack, KeyCode::Left),
+    ("branch", Action::Branch, KeyCode::Char('e')),
+    ("snapshot", Action::Snapshot, KeyCode::Char('s')),
+    ("bookmark", Action::Bookmark, KeyCode::Char('k')),
+    ("jump", Action::Jump, KeyCode::Char('j')),
+    ("chances", Action::Chances, KeyCode::Char('c')),
+    ("reverse", Action::Reverse, KeyCode::Char('b')),
+    ("record_gif", Action::RecordGif, KeyCode::Char('g')),
+    ("screenshot", Action::Screenshot, KeyCode::Char('i')),
+    ("quit", Action::Quit, KeyCode::Esc),
+];
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            editor: Bindings::new(EDITOR_DEFAULTS),
+            prefab: Bindings::new(PREFAB_DEFAULTS),
+            run: Bindings::new(RUN_DEFAULTS),
+        }
+    }
+}
+
+#[test]
+fn test_apply_settings() {
+    let mut settings = Settings::default();
+    settings
+        .apply(
+            "# defaults\nwidth = 40\nalive_char = \"@\" # comment\nrule = \"B36/S23\"\ntopology = \"torus\"\n\n\
+             [colors]\ntheme = \"ocean\"\nalive = \"#00ff00\"\nage = true\n\n[keys.run]\nreset = \"x\"\n",
+        )
+        .unwrap();
+
+    assert_eq!(settings.board_width, 40);
+    assert_eq!(settings.alive_cell, '@');
+    assert_eq!(settings.rule.to_string(), "B36/S23");
+    assert_eq!(settings.topology, Topology::Torus);
+    assert_eq!(settings.theme.alive, Some(Color::Rgb { r: 0, g: 255, b: 0 }));
+    assert!(settings.theme.color_by_age);
+    assert!(settings.theme.ages.is_some());
+    assert_eq!(settings.keys.run.action(KeyCode::Char('x')), Some(Action::Reset));
+    assert_eq!(settings.keys.run.action(KeyCode::Char('r')), None);
+    assert!(settings.apply("[keys.run]\nfly = \"f\"").is_err());
+}
@@ -0,0 +1,234 @@
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers};
+use crossterm::style::{Color, ResetColor, SetBackgroundColor};
+use crossterm::{cursor, terminal, ExecutableCommand};
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvError, RecvTimeoutError, Sender, TryRecvError};
+use std::sync::Once;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::record::out;
+
+/// How long the key reader waits for a key before checking whether it should stop
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Whether the terminal is set up for the UI and needs putting back
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+
+/// Set by Ctrl-C, SIGINT, SIGTERM or SIGHUP to end the session as if it was quit
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Set by SIGHUP, once the terminal has been closed
+static HUNG_UP: AtomicBool = AtomicBool::new(false);
+
+/// Whether the terminal was closed under the session
+pub fn hung_up() -> bool {
+    HUNG_UP.load(Ordering::SeqCst)
+}
+
+/// What the terminal tells the UI
+#[derive(Clone, Copy, Debug)]
+pub enum TermEvent {
+    Key(KeyCode),
+    /// The terminal now has this many columns and rows
+    Resize(u16, u16),
+}
+
+/// The keys pressed on the terminal and its resizes, in order
+pub struct Keys {
+    rx: Receiver<TermEvent>,
+    /// A resize that came while a line was typed, read next
+    held: Option<TermEvent>,
+}
+
+impl Keys {
+    pub fn new(rx: Receiver<TermEvent>) -> Self {
+        Keys { rx, held: None }
+    }
+
+    /// The next key or resize, waiting for it. An error once they stop
+    pub fn recv(&mut self) -> Result<TermEvent, RecvError> {
+        self.wait(None).map_err(|_| RecvError)
+    }
+
+    /// The next key or resize, waiting up to `timeout` for it
+    pub fn recv_timeout(&mut self, timeout: Duration) -> Result<TermEvent, RecvTimeoutError> {
+        self.wait(Some(timeout))
+    }
+
+    /// The next key or resize if there is one already
+    pub fn try_recv(&mut self) -> Result<TermEvent, TryRecvError> {
+        match self.held.take() {
+            Some(event) => Ok(event),
+            None if hung_up() => Err(TryRecvError::Disconnected),
+            None => self.rx.try_recv(),
+        }
+    }
+
+    /// The next key pressed, holding back the latest resize meanwhile to be
+    /// read after it. `None` once the keys stop
+    pub fn key(&mut self) -> Option<KeyCode> {
+        loop {
+            match self.wait_channel(None).ok()? {
+                TermEvent::Key(code) => return Some(code),
+                resize => self.held = Some(resize),
+            }
+        }
+    }
+
+    fn wait(&mut self, timeout: Option<Duration>) -> Result<TermEvent, RecvTimeoutError> {
+        match self.held.take() {
+            Some(event) => Ok(event),
+            None => self.wait_channel(timeout),
+        }
+    }
+
+    /// Waits on the channel a slice at a time, as the key reader can be left
+    /// stuck on a closed terminal without ever dropping its end
+    fn wait_channel(&self, timeout: Option<Duration>) -> Result<TermEvent, RecvTimeoutError> {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        loop {
+            if hung_up() {
+                return Err(RecvTimeoutError::Disconnected);
+            }
+            let slice = match deadline {
+                Some(deadline) => deadline.saturating_duration_since(Instant::now()).min(POLL_INTERVAL),
+                None => POLL_INTERVAL,
+            };
+            match self.rx.recv_timeout(slice) {
+                Err(RecvTimeoutError::Timeout) if deadline.is_none_or(|deadline| Instant::now() < deadline) => {}
+                result => return result,
+            }
+        }
+    }
+}
+
+/// The terminal set up for the UI, in raw mode on the alternate screen with
+/// the cursor hidden, and a thread reading its keys. It is put back the way
+/// it was when dropped, on a panic, and on a second interrupt
+pub struct Terminal {
+    stop_tx: Sender<()>,
+    reader: Option<JoinHandle<()>>,
+}
+
+impl Terminal {
+    /// Sets up the terminal, returning it along with the keys pressed and its
+    /// resizes. They stop once the session is interrupted
+    pub fn start(background: Option<Color>) -> (Self, Keys) {
+        install_handlers();
+        INTERRUPTED.store(false, Ordering::SeqCst);
+
+        let mut out = out();
+        let _ = out.execute(terminal::EnterAlternateScreen);
+        let _ = out.execute(cursor::Hide);
+        if let Some(background) = background {
+            let _ = out.execute(SetBackgroundColor(background));
+        }
+        let _ = terminal::enable_raw_mode();
+        ACTIVE.store(true, Ordering::SeqCst);
+
+        let (key_tx, key_rx) = mpsc::channel();
+        let (stop_tx, stop_rx) = mpsc::channel();
+        let reader = thread::spawn(move || read_keys(key_tx, stop_rx));
+
+        (Terminal { stop_tx, reader: Some(reader) }, Keys::new(key_rx))
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        let _ = self.stop_tx.send(());
+        // crossterm's reader spins on a closed terminal rather than failing,
+        // so it's left behind
+        if let Some(reader) = self.reader.take().filter(|_| !hung_up()) {
+            let _ = reader.join();
+        }
+        if ACTIVE.swap(false, Ordering::SeqCst) {
+            restore(&mut out());
+        }
+    }
+}
+
+/// Sends the keys pressed and resizes to `key_tx` until told to stop or
+/// interrupted. Dropping `key_tx` then tells the UI there are no more keys,
+/// so it quits
+fn read_keys(key_tx: Sender<TermEvent>, stop_rx: Receiver<()>) {
+    while matches!(stop_rx.try_recv(), Err(TryRecvError::Empty)) && !INTERRUPTED.load(Ordering::SeqCst) {
+        match event::poll(POLL_INTERVAL) {
+            Ok(true) => {}
+            Ok(false) => continue,
+            Err(_) => break,
+        }
+
+        let event = match event::read() {
+            // raw mode turns Ctrl-C into a key rather than a signal
+            Ok(Event::Key(KeyEvent { code: KeyCode::Char('c'), modifiers, .. }))
+                if modifiers.contains(KeyModifiers::CONTROL) =>
+            {
+                INTERRUPTED.store(true, Ordering::SeqCst);
+                continue;
+            }
+            Ok(Event::Key(key)) => TermEvent::Key(key.code),
+            Ok(Event::Resize(columns, rows)) => TermEvent::Resize(columns, rows),
+            Ok(_) => continue,
+            Err(_) => break,
+        };
+        if key_tx.send(event).is_err() {
+            break;
+        }
+    }
+}
+
+/// Leaves raw mode and the alternate screen, and shows the cursor again
+fn restore(out: &mut impl Write) {
+    let _ = terminal::disable_raw_mode();
+    let _ = out.execute(ResetColor);
+    let _ = out.execute(cursor::Show);
+    let _ = out.execute(terminal::LeaveAlternateScreen);
+}
+
+/// Puts the terminal back before a panic message is printed, and handles
+/// interrupts while it is set up
+fn install_handlers() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        let default_hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            if ACTIVE.swap(false, Ordering::SeqCst) {
+                restore(&mut std::io::stdout());
+            }
+            default_hook(info);
+        }));
+
+        #[cfg(unix)]
+        handle_signals();
+    });
+}
+
+/// The first SIGINT, SIGTERM or SIGHUP ends the session as if it was quit, so
+/// the journal and recording are finished. A second one, or one while the UI
+/// isn't up, leaves straight away. After SIGHUP nothing more is drawn, as the
+/// terminal is gone
+#[cfg(unix)]
+fn handle_signals() {
+    use signal_hook::consts::{SIGHUP, SIGINT, SIGTERM};
+
+    let Ok(mut signals) = signal_hook::iterator::Signals::new([SIGINT, SIGTERM, SIGHUP]) else {
+        return;
+    };
+    thread::spawn(move || {
+        for signal in signals.forever() {
+            if signal == SIGHUP {
+                crate::record::mute(true);
+                HUNG_UP.store(true, Ordering::SeqCst);
+            }
+            if !ACTIVE.load(Ordering::SeqCst) || INTERRUPTED.swap(true, Ordering::SeqCst) {
+                if ACTIVE.swap(false, Ordering::SeqCst) {
+                    restore(&mut std::io::stdout());
+                }
+                std::process::exit(128 + signal);
+            }
+        }
+    });
+}